@@ -0,0 +1,129 @@
+use eframe::egui;
+
+/// how many jittered samples get averaged into each pixel, one more each frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Antialiasing {
+    Off,
+    /// size x size samples spread evenly over the pixel, which gives the same image every time
+    Grid {
+        size: u32,
+    },
+    /// a quasi-random sequence, which keeps getting better as it goes
+    Random {
+        samples: u32,
+    },
+}
+impl Antialiasing {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Grid { .. } => "grid",
+            Self::Random { .. } => "random",
+        }
+    }
+
+    pub(crate) fn sample_count(&self) -> u32 {
+        match *self {
+            Self::Off => 1,
+            Self::Grid { size } => size * size,
+            Self::Random { samples } => samples,
+        }
+    }
+
+    /// where in the pixel the sample goes, in [-0.5, 0.5] pixels with y going down
+    pub(crate) fn jitter(&self, sample: u32) -> [f32; 2] {
+        match *self {
+            Self::Off => [0.0, 0.0],
+            Self::Grid { size } => {
+                [sample % size, sample / size].map(|cell| (cell as f32 + 0.5) / size as f32 - 0.5)
+            }
+            // the first sample is in the middle so it's the same as without antialiasing
+            Self::Random { .. } if sample == 0 => [0.0, 0.0],
+            Self::Random { .. } => {
+                // the r2 sequence, from the plastic number
+                const G: f64 = 1.324_717_957_244_746;
+                [1.0 / G, 1.0 / (G * G)].map(|a| ((0.5 + sample as f64 * a).fract() - 0.5) as f32)
+            }
+        }
+    }
+
+    /// returns whether anything changed
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let old = *self;
+        egui::ComboBox::from_label("antialiasing")
+            .selected_text(self.name())
+            .show_ui(ui, |ui| {
+                for option in [
+                    Self::Off,
+                    Self::Grid { size: 4 },
+                    Self::Random { samples: 64 },
+                ] {
+                    if ui
+                        .selectable_label(self.name() == option.name(), option.name())
+                        .clicked()
+                        && self.name() != option.name()
+                    {
+                        *self = option;
+                    }
+                }
+            })
+            .response
+            .on_hover_text(
+                "grid is repeatable, for exports, and random refines the interactive view",
+            );
+        match self {
+            Self::Off => {}
+            Self::Grid { size } => {
+                ui.add(egui::Slider::new(size, 1..=16).text("grid size"));
+            }
+            Self::Random { samples } => {
+                ui.add(
+                    egui::Slider::new(samples, 1..=1024)
+                        .logarithmic(true)
+                        .text("samples"),
+                );
+            }
+        }
+        *self != old
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_covers_the_pixel_once() {
+        for size in 1..=16 {
+            let grid = Antialiasing::Grid { size };
+            let mut offsets: Vec<[f32; 2]> = (0..grid.sample_count())
+                .map(|sample| grid.jitter(sample))
+                .collect();
+            assert_eq!(offsets.len() as u32, size * size);
+            assert!(
+                offsets.iter().flatten().all(|offset| offset.abs() < 0.5),
+                "{size}"
+            );
+            offsets.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+            offsets.dedup();
+            assert_eq!(offsets.len() as u32, size * size, "{size}");
+            // evenly spread, so they average out to the middle of the pixel
+            for axis in 0..2 {
+                let mean =
+                    offsets.iter().map(|offset| offset[axis]).sum::<f32>() / offsets.len() as f32;
+                assert!(mean.abs() < 1e-5, "{size} {mean}");
+            }
+        }
+    }
+
+    #[test]
+    fn random_stays_inside_the_pixel() {
+        let random = Antialiasing::Random { samples: 1024 };
+        assert_eq!(random.jitter(0), [0.0, 0.0]);
+        assert!(
+            (0..random.sample_count())
+                .flat_map(|sample| random.jitter(sample))
+                .all(|offset| (-0.5..=0.5).contains(&offset))
+        );
+    }
+}
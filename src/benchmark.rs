@@ -0,0 +1,292 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use eframe::{egui, wgpu};
+
+use crate::{MapResult, map_read};
+
+/// how the iteration pass runs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Backend {
+    /// a full screen draw, with a fragment per pixel
+    Fragment,
+    /// a compute dispatch with a thread per pixel
+    Compute { workgroup_size: [u32; 2] },
+}
+impl Backend {
+    pub(crate) fn name(&self) -> String {
+        match self {
+            Self::Fragment => "fragment".to_owned(),
+            Self::Compute {
+                workgroup_size: [x, y],
+            } => format!("compute {x}x{y}"),
+        }
+    }
+
+    /// the fragment backend and every workgroup size worth trying that fits in limits
+    pub(crate) fn candidates(limits: &wgpu::Limits) -> Vec<Self> {
+        const WORKGROUP_SIZES: [[u32; 2]; 14] = [
+            [8, 1],
+            [32, 1],
+            [64, 1],
+            [256, 1],
+            [4, 4],
+            [8, 4],
+            [4, 8],
+            [8, 8],
+            [16, 4],
+            [32, 4],
+            [16, 8],
+            [8, 16],
+            [32, 8],
+            [16, 16],
+        ];
+        std::iter::once(Self::Fragment)
+            .chain(
+                WORKGROUP_SIZES
+                    .into_iter()
+                    .filter(|&[x, y]| {
+                        x <= limits.max_compute_workgroup_size_x
+                            && y <= limits.max_compute_workgroup_size_y
+                            && x * y <= limits.max_compute_invocations_per_workgroup
+                    })
+                    .map(|workgroup_size| Self::Compute { workgroup_size }),
+            )
+            .collect()
+    }
+}
+
+/// times the iteration pass.
+/// uses timestamp queries when the adapter has them,
+/// otherwise times the whole frame from submitting to the gpu finishing it.
+pub(crate) struct GpuTimer {
+    query_set: Option<wgpu::QuerySet>,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// Some while a time is being read back
+    pending: Option<PendingTime>,
+}
+
+struct PendingTime {
+    submitted: Instant,
+    /// set once the gpu is done with everything submitted up to then
+    finished: Arc<Mutex<Option<Instant>>>,
+    /// with timestamp queries, readback_buffer being mapped
+    mapped: Option<MapResult>,
+}
+
+impl GpuTimer {
+    pub(crate) fn new(device: &wgpu::Device) -> Self {
+        let size = 2 * std::mem::size_of::<u64>() as u64;
+        Self {
+            query_set: device
+                .features()
+                .contains(wgpu::Features::TIMESTAMP_QUERY)
+                .then(|| {
+                    device.create_query_set(&wgpu::QuerySetDescriptor {
+                        label: Some("timer_query_set"),
+                        ty: wgpu::QueryType::Timestamp,
+                        count: 2,
+                    })
+                }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("timer_resolve_buffer"),
+                size,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("timer_readback_buffer"),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            pending: None,
+        }
+    }
+
+    /// whether the times are only the iteration pass, rather than the whole frame
+    pub(crate) fn is_gpu_timed(&self) -> bool {
+        self.query_set.is_some()
+    }
+
+    /// whether a time is still being read back.
+    /// nothing else can be timed until it's in, since it'd copy over the mapped buffer.
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub(crate) fn render_timestamp_writes(&self) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        self.query_set
+            .as_ref()
+            .map(|query_set| wgpu::RenderPassTimestampWrites {
+                query_set,
+                beginning_of_pass_write_index: Some(0),
+                end_of_pass_write_index: Some(1),
+            })
+    }
+
+    pub(crate) fn compute_timestamp_writes(&self) -> Option<wgpu::ComputePassTimestampWrites<'_>> {
+        self.query_set
+            .as_ref()
+            .map(|query_set| wgpu::ComputePassTimestampWrites {
+                query_set,
+                beginning_of_pass_write_index: Some(0),
+                end_of_pass_write_index: Some(1),
+            })
+    }
+
+    /// call after the timed pass and before submitting
+    pub(crate) fn resolve(&self, command_encoder: &mut wgpu::CommandEncoder) {
+        if let Some(query_set) = &self.query_set {
+            command_encoder.resolve_query_set(query_set, 0..2, &self.resolve_buffer, 0);
+            command_encoder.copy_buffer_to_buffer(
+                &self.resolve_buffer,
+                0,
+                &self.readback_buffer,
+                0,
+                self.resolve_buffer.size(),
+            );
+        }
+    }
+
+    /// starts reading back the time of what was submitted at submitted,
+    /// for poll to pick up a frame or so later
+    pub(crate) fn start_readback(&mut self, queue: &wgpu::Queue, submitted: Instant) {
+        let finished = Arc::new(Mutex::new(None));
+        let callback_finished = finished.clone();
+        queue.on_submitted_work_done(move || {
+            *callback_finished.lock().unwrap() = Some(Instant::now());
+        });
+        let mapped = self
+            .query_set
+            .as_ref()
+            .map(|_| map_read(&self.readback_buffer));
+        self.pending = Some(PendingTime {
+            submitted,
+            finished,
+            mapped,
+        });
+    }
+
+    /// the time being read back in seconds, if it's in.
+    /// a failed map drops the time, and the next one can be read back as usual.
+    pub(crate) fn poll(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Option<Result<f64, wgpu::BufferAsyncError>> {
+        let pending = self.pending.as_ref()?;
+        device.poll(wgpu::Maintain::Poll);
+        let finished = (*pending.finished.lock().unwrap())?;
+        let seconds = match &pending.mapped {
+            None => (finished - pending.submitted).as_secs_f64(),
+            Some(mapped) => {
+                let map_result = mapped.lock().unwrap().take()?;
+                if let Err(error) = map_result {
+                    self.pending = None;
+                    return Some(Err(error));
+                }
+                let ticks = {
+                    let timestamps: [u64; 2] = bytemuck::pod_read_unaligned(
+                        &self.readback_buffer.slice(..).get_mapped_range(),
+                    );
+                    timestamps[1].wrapping_sub(timestamps[0])
+                };
+                self.readback_buffer.unmap();
+                ticks as f64 * queue.get_timestamp_period() as f64 * 1e-9
+            }
+        };
+        self.pending = None;
+        Some(Ok(seconds))
+    }
+}
+
+/// renders a few frames with each backend and keeps the median time of each
+pub(crate) struct Benchmark {
+    /// what the times were measured on, since the fastest backend depends on it
+    adapter: String,
+    /// the backends left to try, with the current one first
+    remaining: Vec<Backend>,
+    /// of the current backend
+    samples: Vec<f64>,
+    /// each backend with its median time in seconds
+    results: Vec<(Backend, f64)>,
+}
+impl Benchmark {
+    /// how many frames are kept for each backend.
+    /// the one before them is thrown out since it also pays for making the pipeline.
+    const FRAMES_PER_BACKEND: usize = 9;
+
+    pub(crate) fn new(adapter: String, backends: Vec<Backend>) -> Self {
+        Self {
+            adapter,
+            remaining: backends,
+            samples: Vec::new(),
+            results: Vec::new(),
+        }
+    }
+
+    /// the backend to render the next frame with, or None once it's done
+    pub(crate) fn current(&self) -> Option<Backend> {
+        self.remaining.first().copied()
+    }
+
+    pub(crate) fn record(&mut self, seconds: f64) {
+        let Some(backend) = self.current() else {
+            return;
+        };
+        self.samples.push(seconds);
+        if self.samples.len() <= Self::FRAMES_PER_BACKEND {
+            return;
+        }
+        self.samples.remove(0);
+        self.samples.sort_by(f64::total_cmp);
+        self.results
+            .push((backend, self.samples[self.samples.len() / 2]));
+        self.samples.clear();
+        self.remaining.remove(0);
+    }
+
+    pub(crate) fn fastest(&self) -> Option<Backend> {
+        self.results
+            .iter()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|&(backend, _)| backend)
+    }
+
+    /// the results so far, returns a backend if one got picked
+    pub(crate) fn ui(&self, ui: &mut egui::Ui, gpu_timed: bool) -> Option<Backend> {
+        let mut picked = None;
+        ui.label(format!("on {}", self.adapter));
+        if !gpu_timed {
+            ui.label(
+                "no timestamp queries on this adapter, so these are whole frames timed on the cpu",
+            );
+        }
+        if let Some(backend) = self.current() {
+            ui.label(format!(
+                "measuring {} ({} left)",
+                backend.name(),
+                self.remaining.len()
+            ));
+        }
+        let fastest = self.fastest();
+        egui::Grid::new("benchmark_results").show(ui, |ui| {
+            for &(backend, seconds) in &self.results {
+                if ui
+                    .selectable_label(Some(backend) == fastest, backend.name())
+                    .on_hover_text("use this one")
+                    .clicked()
+                {
+                    picked = Some(backend);
+                }
+                ui.label(format!("{:.3} ms", seconds * 1e3));
+                ui.end_row();
+            }
+        });
+        picked
+    }
+}
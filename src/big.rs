@@ -0,0 +1,662 @@
+use crate::Complex;
+
+/// bits in each limb of a BigFixed
+const LIMB_BITS: i32 = 32;
+/// the most fractional digits from_decimal will take
+const MAX_DECIMAL_DIGITS: usize = 4096;
+
+/// a sign-magnitude fixed point number with as many fractional bits as we want.
+/// this is what keeps track of where we are once f32 and f64 run out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct BigFixed {
+    negative: bool,
+    /// little endian base 2^32 digits.
+    /// the last one is the integer part and the rest are fractional.
+    limbs: Vec<u32>,
+}
+impl BigFixed {
+    pub(crate) fn zero(frac_limbs: usize) -> Self {
+        Self {
+            negative: false,
+            limbs: vec![0; frac_limbs + 1],
+        }
+    }
+
+    pub(crate) fn frac_limbs(&self) -> usize {
+        self.limbs.len() - 1
+    }
+
+    /// pads or truncates the fractional part to exactly frac_limbs limbs
+    pub(crate) fn with_frac_limbs(&self, frac_limbs: usize) -> Self {
+        let old = self.frac_limbs();
+        let limbs = if frac_limbs >= old {
+            let mut limbs = vec![0; frac_limbs - old];
+            limbs.extend_from_slice(&self.limbs);
+            limbs
+        } else {
+            self.limbs[old - frac_limbs..].to_vec()
+        };
+        Self {
+            negative: self.negative,
+            limbs,
+        }
+        .normalized()
+    }
+
+    /// exact as long as x fits in frac_limbs fractional limbs,
+    /// otherwise the low bits are truncated.
+    /// saturates if the integer part of x doesn't fit in a u32.
+    pub(crate) fn from_f64(x: f64, frac_limbs: usize) -> Self {
+        assert!(x.is_finite());
+        if x.abs() >= 4294967296.0 {
+            return Self {
+                negative: x.is_sign_negative(),
+                limbs: vec![u32::MAX; frac_limbs + 1],
+            };
+        }
+        let bits = x.to_bits();
+        let exponent_bits = ((bits >> 52) & 0x7FF) as i32;
+        let fraction = bits & ((1 << 52) - 1);
+        let (mantissa, exponent) = if exponent_bits == 0 {
+            (fraction, -1074)
+        } else {
+            (fraction | (1 << 52), exponent_bits - 1075)
+        };
+
+        let mut limbs = vec![0; frac_limbs + 1];
+        // x * 2^(32 * frac_limbs) = mantissa * 2^shift
+        let shift = exponent + LIMB_BITS * frac_limbs as i32;
+        for bit in 0..53 {
+            if mantissa & (1 << bit) == 0 {
+                continue;
+            }
+            let position = shift + bit;
+            if position < 0 {
+                continue;
+            }
+            let limb = (position / LIMB_BITS) as usize;
+            if limb < limbs.len() {
+                limbs[limb] |= 1 << (position % LIMB_BITS);
+            }
+        }
+        Self {
+            negative: x.is_sign_negative(),
+            limbs,
+        }
+        .normalized()
+    }
+
+    pub(crate) fn to_f64(&self) -> f64 {
+        let frac_limbs = self.frac_limbs() as i32;
+        // only the top few nonzero limbs can affect the result
+        let magnitude = self
+            .limbs
+            .iter()
+            .enumerate()
+            .rev()
+            .skip_while(|&(_, &limb)| limb == 0)
+            .take(3)
+            .fold(0.0, |sum, (i, &limb)| {
+                sum + limb as f64 * 2.0f64.powi(LIMB_BITS * (i as i32 - frac_limbs))
+            });
+        if self.negative { -magnitude } else { magnitude }
+    }
+
+    /// self, or max with self's sign if it's further from zero than that
+    pub(crate) fn clamp_magnitude(&self, max: f64) -> Self {
+        let x = self.to_f64();
+        if x.abs() > max {
+            Self::from_f64(max.copysign(x), self.frac_limbs())
+        } else {
+            self.clone()
+        }
+    }
+
+    pub(crate) fn to_f32(&self) -> f32 {
+        self.to_f64() as f32
+    }
+
+    /// splits self into hi + lo, for the shader's double-single arithmetic
+    pub(crate) fn to_f32_pair(&self) -> (f32, f32) {
+        let hi = self.to_f32();
+        let lo = (self - &Self::from_f64(hi as f64, self.frac_limbs())).to_f32();
+        (hi, lo)
+    }
+
+    /// splits self into hi + mid + lo, which is enough bits for the shader's f64 to add back up
+    /// to the f64 closest to self
+    pub(crate) fn to_f32_triple(&self) -> (f32, f32, f32) {
+        let (hi, mid) = self.to_f32_pair();
+        let rest = &(self - &Self::from_f64(hi as f64, self.frac_limbs()))
+            - &Self::from_f64(mid as f64, self.frac_limbs());
+        (hi, mid, rest.to_f32())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.limbs.iter().all(|&limb| limb == 0)
+    }
+
+    /// makes zero positive so equality works
+    fn normalized(mut self) -> Self {
+        if self.is_zero() {
+            self.negative = false;
+        }
+        self
+    }
+
+    /// compares magnitudes, both must have the same number of limbs
+    fn magnitude_cmp(a: &[u32], b: &[u32]) -> std::cmp::Ordering {
+        debug_assert_eq!(a.len(), b.len());
+        a.iter().rev().cmp(b.iter().rev())
+    }
+
+    /// a + b, saturating if the integer part overflows
+    fn magnitude_add(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut carry = 0u64;
+        let sum: Vec<u32> = a
+            .iter()
+            .zip(b)
+            .map(|(&a, &b)| {
+                let sum = a as u64 + b as u64 + carry;
+                carry = sum >> LIMB_BITS;
+                sum as u32
+            })
+            .collect();
+        if carry != 0 {
+            vec![u32::MAX; sum.len()]
+        } else {
+            sum
+        }
+    }
+
+    /// parses decimals like "-1.25", ".5" and "3e-40", rounding to the nearest representable value.
+    /// keeps at least min_frac_limbs fractional limbs, and more if the digits need them.
+    /// returns None if it isn't a number or the integer part doesn't fit in a u32.
+    pub(crate) fn from_decimal(s: &str, min_frac_limbs: usize) -> Option<Self> {
+        Self::parse_decimal(s, |scale| {
+            // one extra limb so numbers like 3e-40 keep their significant digits
+            min_frac_limbs.max(
+                (scale as f64 * std::f64::consts::LOG2_10 / LIMB_BITS as f64).ceil() as usize + 1,
+            )
+        })
+    }
+
+    /// from_decimal, but frac_limbs picks the precision from the number of fractional digits
+    fn parse_decimal(s: &str, frac_limbs: impl FnOnce(usize) -> usize) -> Option<Self> {
+        let s = s.trim();
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+            None => (s, 0),
+        };
+        let (int_digits, frac_digits) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if int_digits.is_empty() && frac_digits.is_empty() {
+            return None;
+        }
+        // value = digits * 10^-scale
+        let mut digits = vec![0];
+        for c in int_digits.chars().chain(frac_digits.chars()) {
+            uint_mul_small(&mut digits, 10);
+            uint_add(&mut digits, &[c.to_digit(10)?]);
+        }
+        let scale = frac_digits.len() as i64 - exponent as i64;
+        // anything past this is just noise that would make every operation slower,
+        // or doesn't fit in the integer part anyway
+        if scale.unsigned_abs() as usize > MAX_DECIMAL_DIGITS {
+            return None;
+        }
+        for _ in scale..0 {
+            uint_mul_small(&mut digits, 10);
+        }
+        let scale = scale.max(0) as usize;
+        let frac_limbs = frac_limbs(scale);
+
+        // round(digits * 2^(32 * frac_limbs) / 10^scale)
+        let mut limbs = vec![0; frac_limbs];
+        limbs.extend(digits);
+        let mut divisor = vec![1];
+        for _ in 0..scale {
+            uint_mul_small(&mut divisor, 10);
+        }
+        let mut half = divisor.clone();
+        uint_shr1(&mut half);
+        uint_add(&mut limbs, &half);
+        let mut limbs = uint_div(&limbs, &divisor);
+
+        if limbs[frac_limbs + 1..].iter().any(|&limb| limb != 0) {
+            return None;
+        }
+        limbs.resize(frac_limbs + 1, 0);
+        Some(Self { negative, limbs }.normalized())
+    }
+
+    /// the decimal with `digits` fractional digits closest to self
+    fn to_decimal(&self, digits: usize) -> String {
+        // round(self * 10^digits)
+        let mut scaled = self.limbs.clone();
+        for _ in 0..digits {
+            uint_mul_small(&mut scaled, 10);
+        }
+        let mut half = vec![0; self.frac_limbs() + 1];
+        half[self.frac_limbs()] = 1;
+        uint_shr1(&mut half);
+        uint_add(&mut scaled, &half);
+        let mut rounded = scaled[self.frac_limbs()..].to_vec();
+
+        let mut decimal = Vec::new();
+        while decimal.len() <= digits || rounded.iter().any(|&limb| limb != 0) {
+            decimal.push(char::from_digit(uint_div_small(&mut rounded, 10), 10).unwrap());
+        }
+        if digits > 0 {
+            decimal.insert(digits, '.');
+        }
+        if self.negative {
+            decimal.push('-');
+        }
+        decimal.iter().rev().collect()
+    }
+
+    /// a - b, requires a >= b
+    fn magnitude_sub(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut borrow = 0i64;
+        a.iter()
+            .zip(b)
+            .map(|(&a, &b)| {
+                let mut difference = a as i64 - b as i64 - borrow;
+                borrow = 0;
+                if difference < 0 {
+                    difference += 1 << LIMB_BITS;
+                    borrow = 1;
+                }
+                difference as u32
+            })
+            .collect()
+    }
+}
+impl std::fmt::Display for BigFixed {
+    /// prints the shortest decimal that parses back to the same number at this precision.
+    /// a precision like {:.12} rounds to that many digits instead.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(digits) = f.precision() {
+            return f.write_str(&self.to_decimal(digits));
+        }
+        // enough digits to tell every value apart, and the shortest can only be a few less
+        let max_digits = (LIMB_BITS as f64 * self.frac_limbs() as f64 * std::f64::consts::LOG10_2)
+            .ceil() as usize;
+        let shortest = (max_digits.saturating_sub(2)..max_digits)
+            .map(|digits| self.to_decimal(digits))
+            .find(|decimal| {
+                Self::parse_decimal(decimal, |_| self.frac_limbs()).as_ref() == Some(self)
+            })
+            .unwrap_or_else(|| self.to_decimal(max_digits));
+        let trimmed = if shortest.contains('.') {
+            shortest.trim_end_matches('0').trim_end_matches('.')
+        } else {
+            &shortest
+        };
+        f.write_str(if trimmed == "-0" { "0" } else { trimmed })
+    }
+}
+impl std::ops::Neg for &BigFixed {
+    type Output = BigFixed;
+
+    fn neg(self) -> BigFixed {
+        BigFixed {
+            negative: !self.negative,
+            limbs: self.limbs.clone(),
+        }
+        .normalized()
+    }
+}
+impl std::ops::Add for &BigFixed {
+    type Output = BigFixed;
+
+    /// the result has the precision of the more precise argument
+    fn add(self, other: &BigFixed) -> BigFixed {
+        let frac_limbs = self.frac_limbs().max(other.frac_limbs());
+        let a = self.with_frac_limbs(frac_limbs);
+        let b = other.with_frac_limbs(frac_limbs);
+        if a.negative == b.negative {
+            return BigFixed {
+                negative: a.negative,
+                limbs: BigFixed::magnitude_add(&a.limbs, &b.limbs),
+            }
+            .normalized();
+        }
+        let (big, small) = match BigFixed::magnitude_cmp(&a.limbs, &b.limbs) {
+            std::cmp::Ordering::Less => (b, a),
+            _ => (a, b),
+        };
+        BigFixed {
+            negative: big.negative,
+            limbs: BigFixed::magnitude_sub(&big.limbs, &small.limbs),
+        }
+        .normalized()
+    }
+}
+impl std::ops::Sub for &BigFixed {
+    type Output = BigFixed;
+
+    fn sub(self, other: &BigFixed) -> BigFixed {
+        self + &-other
+    }
+}
+impl std::ops::Mul for &BigFixed {
+    type Output = BigFixed;
+
+    /// truncates to the precision of the more precise argument,
+    /// saturating if the integer part overflows
+    fn mul(self, other: &BigFixed) -> BigFixed {
+        let frac_limbs = self.frac_limbs().max(other.frac_limbs());
+        // the full product has self.frac_limbs() + other.frac_limbs() fractional limbs
+        let drop = self.frac_limbs() + other.frac_limbs() - frac_limbs;
+        let mut product = vec![0u64; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let sum = product[i + j] + a as u64 * b as u64 + carry;
+                product[i + j] = sum & 0xFFFF_FFFF;
+                carry = sum >> LIMB_BITS;
+            }
+            product[i + other.limbs.len()] += carry;
+        }
+        let overflowed = product[drop + frac_limbs + 1..]
+            .iter()
+            .any(|&limb| limb != 0);
+        BigFixed {
+            negative: self.negative != other.negative,
+            limbs: if overflowed {
+                vec![u32::MAX; frac_limbs + 1]
+            } else {
+                product[drop..drop + frac_limbs + 1]
+                    .iter()
+                    .map(|&limb| limb as u32)
+                    .collect()
+            },
+        }
+        .normalized()
+    }
+}
+
+// unsigned big integer helpers for converting to and from decimal,
+// little endian base 2^32 like BigFixed
+
+fn uint_mul_small(a: &mut Vec<u32>, b: u32) {
+    let mut carry = 0u64;
+    for limb in a.iter_mut() {
+        let product = *limb as u64 * b as u64 + carry;
+        *limb = product as u32;
+        carry = product >> LIMB_BITS;
+    }
+    if carry != 0 {
+        a.push(carry as u32);
+    }
+}
+
+/// a += b
+fn uint_add(a: &mut Vec<u32>, b: &[u32]) {
+    if a.len() < b.len() {
+        a.resize(b.len(), 0);
+    }
+    let mut carry = 0u64;
+    for (i, limb) in a.iter_mut().enumerate() {
+        let sum = *limb as u64 + b.get(i).copied().unwrap_or(0) as u64 + carry;
+        *limb = sum as u32;
+        carry = sum >> LIMB_BITS;
+    }
+    if carry != 0 {
+        a.push(carry as u32);
+    }
+}
+
+fn uint_shr1(a: &mut [u32]) {
+    let mut carry = 0;
+    for limb in a.iter_mut().rev() {
+        let next_carry = *limb & 1;
+        *limb = (*limb >> 1) | (carry << (LIMB_BITS - 1));
+        carry = next_carry;
+    }
+}
+
+/// a /= b, returns the remainder
+fn uint_div_small(a: &mut [u32], b: u32) -> u32 {
+    let mut remainder = 0u64;
+    for limb in a.iter_mut().rev() {
+        let current = (remainder << LIMB_BITS) | *limb as u64;
+        *limb = (current / b as u64) as u32;
+        remainder = current % b as u64;
+    }
+    remainder as u32
+}
+
+/// floor(a / b), long division one bit at a time.
+/// slow, but only used when parsing
+fn uint_div(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut quotient = vec![0; a.len()];
+    let mut remainder = vec![0; b.len() + 1];
+    let padded_b = {
+        let mut padded_b = b.to_vec();
+        padded_b.push(0);
+        padded_b
+    };
+    for bit in (0..a.len() * LIMB_BITS as usize).rev() {
+        // remainder = remainder * 2 + bit
+        let mut carry = (a[bit / LIMB_BITS as usize] >> (bit % LIMB_BITS as usize)) & 1;
+        for limb in remainder.iter_mut() {
+            let next_carry = *limb >> (LIMB_BITS - 1);
+            *limb = (*limb << 1) | carry;
+            carry = next_carry;
+        }
+        if BigFixed::magnitude_cmp(&remainder, &padded_b) != std::cmp::Ordering::Less {
+            remainder = BigFixed::magnitude_sub(&remainder, &padded_b);
+            quotient[bit / LIMB_BITS as usize] |= 1 << (bit % LIMB_BITS as usize);
+        }
+    }
+    quotient
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct BigComplex {
+    pub(crate) real: BigFixed,
+    pub(crate) imag: BigFixed,
+}
+impl BigComplex {
+    pub(crate) fn zero(frac_limbs: usize) -> Self {
+        Self {
+            real: BigFixed::zero(frac_limbs),
+            imag: BigFixed::zero(frac_limbs),
+        }
+    }
+
+    pub(crate) fn from_complex(z: Complex, frac_limbs: usize) -> Self {
+        Self {
+            real: BigFixed::from_f64(z.real, frac_limbs),
+            imag: BigFixed::from_f64(z.imag, frac_limbs),
+        }
+    }
+
+    pub(crate) fn to_complex(&self) -> Complex {
+        Complex {
+            real: self.real.to_f64(),
+            imag: self.imag.to_f64(),
+        }
+    }
+
+    pub(crate) fn frac_limbs(&self) -> usize {
+        self.real.frac_limbs().max(self.imag.frac_limbs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> BigFixed {
+        BigFixed::from_decimal(s, 2).unwrap()
+    }
+
+    #[test]
+    fn decimal_round_trips() {
+        for s in [
+            "0",
+            "1",
+            "1.5",
+            "-0.75",
+            "0.1",
+            "-1.7490234375",
+            "3e-40",
+            "-0.123456789012345678901234567890123456789",
+            "4294967295",
+            "-4294967295.5",
+        ] {
+            let x = parse(s);
+            let printed = x.to_string();
+            assert_eq!(parse(&printed), x, "{s} printed as {printed}");
+        }
+        assert_eq!(parse("1.5").to_string(), "1.5");
+        assert_eq!(parse("-0.75").to_string(), "-0.75");
+        assert_eq!(parse("3e-40").to_f64(), 3e-40);
+    }
+
+    #[test]
+    fn decimal_signs_and_errors() {
+        assert_eq!(parse("-0"), BigFixed::zero(2));
+        assert_eq!(parse("+2"), parse("2"));
+        assert_eq!(parse(".5").to_f64(), 0.5);
+        assert_eq!(parse("-2.5e1").to_f64(), -25.0);
+        assert_eq!(parse("-4294967295").to_f64(), -4294967295.0);
+        assert_eq!(BigFixed::from_decimal("4294967296", 2), None);
+        assert_eq!(BigFixed::from_decimal("-4294967296", 2), None);
+        assert_eq!(BigFixed::from_decimal("", 2), None);
+        assert_eq!(BigFixed::from_decimal("-", 2), None);
+        assert_eq!(BigFixed::from_decimal("1.2.3", 2), None);
+        assert_eq!(BigFixed::from_decimal("abc", 2), None);
+    }
+
+    #[test]
+    fn decimal_rounds_to_nearest() {
+        let one_limb = |s| BigFixed::parse_decimal(s, |_| 1).unwrap().limbs;
+        // 0.1 * 2^32 = 429496729.6
+        assert_eq!(one_limb("0.1"), [429496730, 0]);
+        // 0.3 * 2^32 = 1288490188.8
+        assert_eq!(one_limb("0.3"), [1288490189, 0]);
+        // -0.2 * 2^32 = -858993459.2
+        assert_eq!(one_limb("-0.2"), [858993459, 0]);
+        assert_eq!(parse("1.25").to_decimal(1), "1.3");
+        assert_eq!(parse("-1.25").to_decimal(1), "-1.3");
+    }
+
+    #[test]
+    fn from_f64_is_exact() {
+        for x in [0.0, 1.0, -1.5, 0.1, -0.1, 4294967295.0, -4294967295.25] {
+            assert_eq!(BigFixed::from_f64(x, 4).to_f64(), x, "{x}");
+        }
+        // its lowest bit is 2^-152
+        assert_eq!(BigFixed::from_f64(1e-30, 5).to_f64(), 1e-30);
+        // the smallest subnormal is 2^-1074, which is bit 14 of the lowest of 34 limbs
+        let subnormal = BigFixed::from_f64(5e-324, 34);
+        assert_eq!(subnormal.limbs[0], 1 << 14);
+        assert!(subnormal.limbs[1..].iter().all(|&limb| limb == 0));
+        assert_eq!(
+            BigFixed::from_f64(0.1, 4),
+            parse(&format!("{:.80}", 0.1)).with_frac_limbs(4)
+        );
+    }
+
+    #[test]
+    fn from_f64_saturates() {
+        let max = 4294967296.0 - 2.0f64.powi(-32);
+        assert_eq!(BigFixed::from_f64(1e20, 1).to_f64(), max);
+        assert_eq!(BigFixed::from_f64(-1e20, 1).to_f64(), -max);
+        assert_eq!(BigFixed::from_f64(4294967296.0, 1).to_f64(), max);
+    }
+
+    #[test]
+    fn add_and_sub_with_signs() {
+        let cases = [
+            (1.5, 2.25),
+            (1.5, -2.25),
+            (-1.5, 2.25),
+            (-1.5, -2.25),
+            (0.5, -0.5),
+        ];
+        for (a, b) in cases {
+            let (x, y) = (BigFixed::from_f64(a, 2), BigFixed::from_f64(b, 2));
+            assert_eq!((&x + &y).to_f64(), a + b, "{a} + {b}");
+            assert_eq!((&x - &y).to_f64(), a - b, "{a} - {b}");
+        }
+        // carries across limbs, and mixed precisions
+        let tiny = BigFixed::from_f64(2.0f64.powi(-64), 2);
+        let one = BigFixed::from_f64(1.0, 0);
+        assert_eq!((&(&one + &tiny) - &one), tiny);
+        assert_eq!((&one - &tiny).to_f64(), 1.0 - 2.0f64.powi(-64));
+        assert!(!(&BigFixed::from_f64(0.5, 1) + &BigFixed::from_f64(-0.5, 1)).negative);
+    }
+
+    #[test]
+    fn add_saturates() {
+        let big = BigFixed::from_f64(4294967295.0, 1);
+        assert_eq!((&big + &big).to_f64(), 4294967296.0 - 2.0f64.powi(-32));
+        assert!((&-&big - &big).to_f64() < -4294967295.0);
+    }
+
+    #[test]
+    fn mul_with_signs() {
+        let cases = [
+            (1.5, 2.25),
+            (1.5, -2.25),
+            (-1.5, -2.25),
+            (0.0, -3.0),
+            (0.1, 0.1),
+        ];
+        for (a, b) in cases {
+            let (x, y) = (BigFixed::from_f64(a, 4), BigFixed::from_f64(b, 4));
+            assert_eq!((&x * &y).to_f64(), a * b, "{a} * {b}");
+        }
+        assert!(!(&BigFixed::from_f64(0.0, 1) * &BigFixed::from_f64(-1.0, 1)).negative);
+        // 2^-40 * 2^-40 needs more than two fractional limbs, so it truncates to zero
+        let small = BigFixed::from_f64(2.0f64.powi(-40), 2);
+        assert_eq!(&small * &small, BigFixed::zero(2));
+    }
+
+    #[test]
+    fn mul_saturates() {
+        let big = BigFixed::from_f64(65536.0, 1);
+        assert!((&big * &big).to_f64() > 4294967295.0);
+        assert!((&big * &-&big).to_f64() < -4294967295.0);
+    }
+
+    #[test]
+    fn f32_pair_and_triple() {
+        let x = parse("-1.7490234375000000001234567890123");
+        let (hi, lo) = x.to_f32_pair();
+        assert_eq!(hi, -1.7490234);
+        assert!((hi as f64 + lo as f64 - x.to_f64()).abs() < 1e-14);
+        let y = parse("0.1234567890123456789");
+        let (hi, mid, lo) = y.to_f32_triple();
+        assert_eq!(hi as f64 + mid as f64 + lo as f64, y.to_f64());
+    }
+
+    #[test]
+    fn clamp_magnitude() {
+        let x = |x| BigFixed::from_f64(x, 2);
+        assert_eq!(x(3.0).clamp_magnitude(2.0), x(2.0));
+        assert_eq!(x(-3.0).clamp_magnitude(2.0), x(-2.0));
+        assert_eq!(x(1.5).clamp_magnitude(2.0), x(1.5));
+    }
+
+    #[test]
+    fn long_division() {
+        // 2^32 / 3
+        assert_eq!(uint_div(&[0, 1], &[3]), [1431655765, 0]);
+        // (2^64 - 1) / (2^32 + 1) = 2^32 - 1
+        assert_eq!(uint_div(&[u32::MAX, u32::MAX], &[1, 1]), [u32::MAX, 0]);
+        assert_eq!(uint_div(&[7], &[8]), [0]);
+        let mut a = vec![0, 1];
+        assert_eq!(uint_div_small(&mut a, 10), 6);
+        assert_eq!(a, [429496729, 0]);
+    }
+}
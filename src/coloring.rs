@@ -0,0 +1,552 @@
+use std::sync::Arc;
+
+use eframe::egui;
+
+use crate::{Complex, Palette, TextureImage};
+
+/// what the depth goes through before it's wrapped around the color function
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CycleMode {
+    None = 0,
+    Log = 1,
+    LogLog = 2,
+    /// the fraction of escaped pixels that are shallower, so the colors spread evenly at any zoom
+    Histogram = 3,
+}
+impl CycleMode {
+    const ALL: [Self; 4] = [Self::None, Self::Log, Self::LogLog, Self::Histogram];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Log => "log",
+            Self::LogLog => "log log",
+            Self::Histogram => "histogram",
+        }
+    }
+}
+
+/// turns the cycled depth in [0, 1) into a color
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ColorFunction {
+    Linear = 0,
+    Turbo = 1,
+    CyclicTurbo = 2,
+    Rainbow = 3,
+    Palette = 4,
+}
+impl ColorFunction {
+    const ALL: [Self; 5] = [
+        Self::Linear,
+        Self::Turbo,
+        Self::CyclicTurbo,
+        Self::Rainbow,
+        Self::Palette,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::Turbo => "turbo",
+            Self::CyclicTurbo => "cyclic turbo",
+            Self::Rainbow => "rainbow",
+            Self::Palette => "palette",
+        }
+    }
+}
+
+/// how the distance to the set changes the exterior coloring
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum DistanceMode {
+    None = 0,
+    /// black lines along the boundary on white, which stay crisp at any zoom
+    Boundary = 1,
+    /// the depth coloring, fading to black away from the boundary
+    Glow = 2,
+    /// the depth coloring, fading to transparent away from the boundary
+    Alpha = 3,
+}
+impl DistanceMode {
+    const ALL: [Self; 4] = [Self::None, Self::Boundary, Self::Glow, Self::Alpha];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Boundary => "boundary",
+            Self::Glow => "glow",
+            Self::Alpha => "alpha",
+        }
+    }
+}
+
+/// how the points that don't escape get colored
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum InteriorMode {
+    Black = 0,
+    /// by the period of the cycle the orbit falls into
+    Period = 1,
+    /// by the size of the cycle's multiplier, which is 0 at the centers of the components
+    Multiplier = 2,
+    /// by the angle of the cycle's multiplier
+    MultiplierAngle = 3,
+}
+impl InteriorMode {
+    const ALL: [Self; 4] = [
+        Self::Black,
+        Self::Period,
+        Self::Multiplier,
+        Self::MultiplierAngle,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Black => "black",
+            Self::Period => "period",
+            Self::Multiplier => "|multiplier|",
+            Self::MultiplierAngle => "multiplier angle",
+        }
+    }
+}
+
+/// what gets averaged over the orbit to color the exterior instead of the depth
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum AverageMode {
+    None = 0,
+    /// how much z points in a direction
+    Stripe = 1,
+    /// where |z| lands between the bounds the triangle inequality gives it
+    Triangle = 2,
+    /// how sharply the orbit turns
+    Curvature = 3,
+}
+impl AverageMode {
+    const ALL: [Self; 4] = [Self::None, Self::Stripe, Self::Triangle, Self::Curvature];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Stripe => "stripe",
+            Self::Triangle => "triangle inequality",
+            Self::Curvature => "curvature",
+        }
+    }
+}
+
+/// where the loaded image gets mapped
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TextureMode {
+    None = 0,
+    /// by the external angle across and the smooth depth along
+    Exterior = 1,
+    /// by the final z
+    Interior = 2,
+}
+impl TextureMode {
+    const ALL: [Self; 3] = [Self::None, Self::Exterior, Self::Interior];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Exterior => "exterior",
+            Self::Interior => "interior",
+        }
+    }
+}
+
+/// shades the exterior by the angle of z when it escaped,
+/// on top of the depth or average coloring
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum DecompositionMode {
+    None = 0,
+    /// by the sign of imag(z)
+    Binary = 1,
+    /// by which of decomposition_order sectors z lands in
+    Nary = 2,
+    /// lines along the angles that are multiples of 1 / decomposition_order
+    FieldLines = 3,
+    /// by the angle itself
+    ExternalAngle = 4,
+}
+impl DecompositionMode {
+    const ALL: [Self; 5] = [
+        Self::None,
+        Self::Binary,
+        Self::Nary,
+        Self::FieldLines,
+        Self::ExternalAngle,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Binary => "binary",
+            Self::Nary => "n-ary",
+            Self::FieldLines => "field lines",
+            Self::ExternalAngle => "external angle",
+        }
+    }
+}
+
+/// what the lighting takes as the height of the surface
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum LightingMode {
+    None = 0,
+    /// the smooth depth, which works for every fractal
+    Depth = 1,
+    /// the distance estimate, which is smoother but needs the derivative
+    Distance = 2,
+}
+impl LightingMode {
+    const ALL: [Self; 3] = [Self::None, Self::Depth, Self::Distance];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Depth => "depth",
+            Self::Distance => "distance estimate",
+        }
+    }
+}
+
+/// how a fractal's depths get colored.
+/// changing this only reruns the coloring pass, except for the averages.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Coloring {
+    pub(crate) cycle_mode: CycleMode,
+    pub(crate) color_function: ColorFunction,
+    /// the fractions of the shallowest and deepest escaped pixels that get the end colors
+    pub(crate) histogram_clip_low: f32,
+    pub(crate) histogram_clip_high: f32,
+    /// shifts the colors along the color function, in cycles
+    pub(crate) phase: f32,
+    /// how many cycles per unit of cycled depth
+    pub(crate) density: f32,
+    /// used by ColorFunction::Palette
+    pub(crate) palette: Palette,
+    /// uses the distance estimate from the orbit's derivative
+    pub(crate) distance_mode: DistanceMode,
+    /// how far from the boundary the distance mode reaches, in pixels
+    pub(crate) distance_thickness: f32,
+    pub(crate) interior_mode: InteriorMode,
+    /// accumulated while iterating, so changing this or stripe_density reruns the iteration pass
+    pub(crate) average_mode: AverageMode,
+    /// how many stripes there are around the circle
+    pub(crate) stripe_density: f32,
+    pub(crate) decomposition_mode: DecompositionMode,
+    /// how many sectors or field lines there are around the circle
+    pub(crate) decomposition_order: u32,
+    /// how much the decomposition darkens the coloring under it
+    pub(crate) decomposition_strength: f32,
+    /// replaces the exterior or interior coloring with the image
+    pub(crate) texture_mode: TextureMode,
+    /// where the image was loaded from
+    pub(crate) texture_path: String,
+    /// shared so cloning and comparing the coloring stays cheap
+    pub(crate) texture: Arc<TextureImage>,
+    /// shades the exterior as if it were a surface, after everything else
+    pub(crate) lighting_mode: LightingMode,
+    /// where the light comes from around the screen, in radians
+    pub(crate) light_angle: f32,
+    /// how high the light is above the screen, in radians
+    pub(crate) light_elevation: f32,
+    pub(crate) specular: f32,
+    /// how steep the surface is
+    pub(crate) relief: f32,
+}
+impl Default for Coloring {
+    fn default() -> Self {
+        Self {
+            cycle_mode: CycleMode::Log,
+            color_function: ColorFunction::Rainbow,
+            histogram_clip_low: 0.01,
+            histogram_clip_high: 0.01,
+            phase: 0.0,
+            density: 1.0,
+            palette: Palette::default(),
+            distance_mode: DistanceMode::None,
+            distance_thickness: 1.0,
+            interior_mode: InteriorMode::Black,
+            average_mode: AverageMode::None,
+            stripe_density: 5.0,
+            decomposition_mode: DecompositionMode::None,
+            decomposition_order: 8,
+            decomposition_strength: 0.5,
+            texture_mode: TextureMode::None,
+            texture_path: String::new(),
+            texture: Arc::new(TextureImage::blank()),
+            lighting_mode: LightingMode::None,
+            light_angle: std::f32::consts::FRAC_PI_4,
+            light_elevation: std::f32::consts::FRAC_PI_4,
+            specular: 0.5,
+            relief: 1.0,
+        }
+    }
+}
+impl Coloring {
+    /// whether the iteration pass needs to rerun to go from other to self
+    pub(crate) fn needs_iteration(&self, other: &Self) -> bool {
+        self.average_mode != other.average_mode
+            || (self.average_mode == AverageMode::Stripe
+                && self.stripe_density != other.stripe_density)
+    }
+
+    /// name is the fractal's, for the palette editor's title.
+    /// returns whether anything changed.
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui, name: &str) -> bool {
+        let old = self.clone();
+        egui::ComboBox::from_label("cycle mode")
+            .selected_text(self.cycle_mode.name())
+            .show_ui(ui, |ui| {
+                for option in CycleMode::ALL {
+                    ui.selectable_value(&mut self.cycle_mode, option, option.name());
+                }
+            });
+        if self.cycle_mode == CycleMode::Histogram {
+            ui.add(egui::Slider::new(&mut self.histogram_clip_low, 0.0..=0.5).text("clip shallow"));
+            ui.add(egui::Slider::new(&mut self.histogram_clip_high, 0.0..=0.5).text("clip deep"));
+        }
+        egui::ComboBox::from_label("color function")
+            .selected_text(self.color_function.name())
+            .show_ui(ui, |ui| {
+                for option in ColorFunction::ALL {
+                    ui.selectable_value(&mut self.color_function, option, option.name());
+                }
+            });
+        ui.add(egui::Slider::new(&mut self.phase, 0.0..=1.0).text("phase"));
+        ui.add(
+            egui::Slider::new(&mut self.density, 0.001..=100.0)
+                .logarithmic(true)
+                .text("density"),
+        );
+        if self.color_function == ColorFunction::Palette {
+            let editor_id = ui.make_persistent_id("palette_editor");
+            let mut editor_open = ui.data(|data| data.get_temp::<bool>(editor_id).unwrap_or(false));
+            editor_open |= ui.button("edit palette").clicked();
+            if editor_open {
+                self.palette
+                    .editor_ui(ui.ctx(), format!("{name} palette"), &mut editor_open);
+            }
+            ui.data_mut(|data| data.insert_temp(editor_id, editor_open));
+        }
+        egui::ComboBox::from_label("distance estimate")
+            .selected_text(self.distance_mode.name())
+            .show_ui(ui, |ui| {
+                for option in DistanceMode::ALL {
+                    ui.selectable_value(&mut self.distance_mode, option, option.name());
+                }
+            })
+            .response
+            .on_hover_text("only meaningful for fractals that move smoothly with the pixel, so not the meta-fractals");
+        if self.distance_mode != DistanceMode::None {
+            ui.add(
+                egui::Slider::new(&mut self.distance_thickness, 0.1..=100.0)
+                    .logarithmic(true)
+                    .text("thickness (pixels)"),
+            );
+        }
+        egui::ComboBox::from_label("average")
+            .selected_text(self.average_mode.name())
+            .show_ui(ui, |ui| {
+                for option in AverageMode::ALL {
+                    ui.selectable_value(&mut self.average_mode, option, option.name());
+                }
+            });
+        if self.average_mode == AverageMode::Stripe {
+            ui.add(
+                egui::Slider::new(&mut self.stripe_density, 1.0..=16.0)
+                    .text("stripe density")
+                    .clamping(egui::SliderClamping::Never),
+            );
+        }
+        egui::ComboBox::from_label("decomposition")
+            .selected_text(self.decomposition_mode.name())
+            .show_ui(ui, |ui| {
+                for option in DecompositionMode::ALL {
+                    ui.selectable_value(&mut self.decomposition_mode, option, option.name());
+                }
+            });
+        if self.decomposition_mode != DecompositionMode::None {
+            if matches!(
+                self.decomposition_mode,
+                DecompositionMode::Nary | DecompositionMode::FieldLines
+            ) {
+                ui.add(egui::Slider::new(&mut self.decomposition_order, 2..=64).text("order"));
+            }
+            ui.add(
+                egui::Slider::new(&mut self.decomposition_strength, 0.0..=1.0)
+                    .text("decomposition strength"),
+            );
+        }
+        egui::ComboBox::from_label("interior")
+            .selected_text(self.interior_mode.name())
+            .show_ui(ui, |ui| {
+                for option in InteriorMode::ALL {
+                    ui.selectable_value(&mut self.interior_mode, option, option.name());
+                }
+            })
+            .response
+            .on_hover_text(
+                "uses the cycles found while iterating, which only the single precision looks for",
+            );
+        egui::ComboBox::from_label("texture")
+            .selected_text(self.texture_mode.name())
+            .show_ui(ui, |ui| {
+                for option in TextureMode::ALL {
+                    ui.selectable_value(&mut self.texture_mode, option, option.name());
+                }
+            });
+        if self.texture_mode != TextureMode::None {
+            let status_id = ui.make_persistent_id("texture_status");
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.texture_path);
+                if ui.button("load png").clicked() {
+                    let status = TextureImage::load(self.texture_path.as_ref()).map(|image| {
+                        let status = format!("loaded {}x{}", image.width, image.height);
+                        self.texture = Arc::new(image);
+                        status
+                    });
+                    ui.data_mut(|data| data.insert_temp(status_id, status.unwrap_or_else(|e| e)));
+                }
+            });
+            if let Some(status) = ui.data(|data| data.get_temp::<String>(status_id)) {
+                ui.label(status);
+            }
+        }
+        egui::ComboBox::from_label("lighting")
+            .selected_text(self.lighting_mode.name())
+            .show_ui(ui, |ui| {
+                for option in LightingMode::ALL {
+                    ui.selectable_value(&mut self.lighting_mode, option, option.name());
+                }
+            });
+        if self.lighting_mode != LightingMode::None {
+            ui.add(
+                egui::Slider::new(&mut self.light_angle, 0.0..=std::f32::consts::TAU)
+                    .text("light angle"),
+            );
+            ui.add(
+                egui::Slider::new(&mut self.light_elevation, 0.0..=std::f32::consts::FRAC_PI_2)
+                    .text("light elevation"),
+            );
+            ui.add(egui::Slider::new(&mut self.specular, 0.0..=1.0).text("specular"));
+            ui.add(
+                egui::Slider::new(&mut self.relief, 0.01..=100.0)
+                    .logarithmic(true)
+                    .text("relief"),
+            );
+        }
+        *self != old
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TrapShape {
+    None = 0,
+    Point = 1,
+    Line = 2,
+    Cross = 3,
+    Circle = 4,
+    /// pickover stalks, the cross drawn on top of the usual coloring
+    Stalks = 5,
+}
+impl TrapShape {
+    const ALL: [Self; 6] = [
+        Self::None,
+        Self::Point,
+        Self::Line,
+        Self::Cross,
+        Self::Circle,
+        Self::Stalks,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Point => "point",
+            Self::Line => "line",
+            Self::Cross => "cross",
+            Self::Circle => "circle",
+            Self::Stalks => "stalks",
+        }
+    }
+}
+
+/// colors by how close the orbit gets to a shape.
+/// unlike Coloring this is tracked while iterating, so changing it reruns the iteration pass.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct OrbitTrap {
+    pub(crate) shape: TrapShape,
+    pub(crate) point: Complex,
+    /// of the line and the cross, in radians
+    pub(crate) angle: f32,
+    /// of the circle, and how thick the stalks are
+    pub(crate) radius: f32,
+    /// whether the point follows the global point
+    pub(crate) follow_point: bool,
+}
+impl Default for OrbitTrap {
+    fn default() -> Self {
+        Self {
+            shape: TrapShape::None,
+            point: Complex::ZERO,
+            angle: 0.0,
+            radius: 0.1,
+            follow_point: false,
+        }
+    }
+}
+impl OrbitTrap {
+    /// returns whether anything changed
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let old = self.clone();
+        egui::ComboBox::from_label("trap shape")
+            .selected_text(self.shape.name())
+            .show_ui(ui, |ui| {
+                for option in TrapShape::ALL {
+                    ui.selectable_value(&mut self.shape, option, option.name());
+                }
+            });
+        if self.shape == TrapShape::None {
+            return *self != old;
+        }
+        ui.checkbox(&mut self.follow_point, "follow point")
+            .on_hover_text("move the trap with the global point");
+        ui.add_enabled_ui(!self.follow_point, |ui| {
+            ui.add(
+                egui::Slider::new(&mut self.point.real, -2.0..=2.0)
+                    .text("trap real")
+                    .clamping(egui::SliderClamping::Never),
+            );
+            ui.add(
+                egui::Slider::new(&mut self.point.imag, -2.0..=2.0)
+                    .text("trap imag")
+                    .clamping(egui::SliderClamping::Never),
+            );
+        });
+        if matches!(
+            self.shape,
+            TrapShape::Line | TrapShape::Cross | TrapShape::Stalks
+        ) {
+            ui.add(
+                egui::Slider::new(&mut self.angle, 0.0..=std::f32::consts::PI).text("trap angle"),
+            );
+        }
+        if matches!(self.shape, TrapShape::Circle | TrapShape::Stalks) {
+            ui.add(
+                egui::Slider::new(&mut self.radius, 0.001..=2.0)
+                    .logarithmic(true)
+                    .text("trap radius"),
+            );
+        }
+        *self != old
+    }
+}
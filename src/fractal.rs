@@ -1,39 +1,290 @@
-use std::sync::Arc;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+    thread::JoinHandle,
+};
 
 use eframe::{
     egui,
     wgpu::{self, util::DeviceExt},
 };
 
-use crate::{Camera, Complex};
+use crate::{
+    Antialiasing, Backend, Benchmark, BigComplex, BigFixed, Camera, Coloring, Complex, CycleMode,
+    FractalRenderer, GpuTimer, Histogram, IterationStats, MAX_CENTER, OrbitTrap, PALETTE_SIZE,
+    StatCounts, TEXTURE_FORMAT, TextureImage,
+};
 
 const VELOCITY_DAMPING: f32 = 0.9999;
+/// f64 loses its exponent range around here, and the deep zoom needs f64 radii
+const MIN_RADIUS: f64 = 1e-300;
+/// zoomed out this far the whole fractal is a dot, and the pans stay well within MAX_CENTER
+const MAX_RADIUS: f64 = 1e3;
+/// keeps escape_radius_2 finite as the exponent approaches 1
+const MAX_ESCAPE_RADIUS: f32 = 1e18;
+/// the integer part of a BigFixed is only 32 bits, so squaring the reference orbit
+/// overflows not far past this. perturbation caps the escape radius to it so the pixels
+/// don't outlive the reference just for having a bigger one.
+const MAX_PERTURBATION_ESCAPE_RADIUS: f32 = 16384.0;
+/// the size of an Orbit in shader.wgsl, which the iteration pass writes one of per pixel
+const ORBIT_SIZE: u64 = 48;
+/// the size of a Resume in shader.wgsl, which the single precision keeps one of per pixel
+const RESUME_SIZE: u64 = 56;
+/// a guess at how many iterations a millisecond fits before the first slice is timed,
+/// counting every pixel as going all the way to max_depth
+const INITIAL_ITERATION_RATE: f64 = 6e7;
+/// the top of the max depth slider, and as far as keep iterating and auto depth go
+const MAX_DEPTH: u32 = 1 << 24;
+/// how many times a reference gets moved onto a glitched pixel before the glitches are left be
+const MAX_GLITCH_MOVES: u32 = 4;
+/// auto depth doesn't go below this, so zooming back out doesn't look blocky for a frame
+const MIN_AUTO_DEPTH: u32 = 64;
+/// auto depth doubles max_depth when more than this fraction of the pixels escaped late
+const AUTO_DEPTH_RAISE_FRACTION: f32 = 1e-3;
+/// and halves it when less than this fraction escaped past a quarter of it.
+/// halving makes the new late ones the old past a quarter ones,
+/// so it's low enough to not double straight back.
+const AUTO_DEPTH_LOWER_FRACTION: f32 = 1e-4;
 
 #[repr(u32)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub(crate) enum FractalType {
-    Mandelbrot { z0: Complex } = 0,
+    Mandelbrot { z0: Complex, exponent: f32 } = 0,
     Metabrot { sub_fractal_width: u32 } = 1,
-    JuliaSet { c: Complex } = 2,
+    JuliaSet { c: Complex, exponent: f32 } = 2,
     MetaJulia { sub_fractal_width: u32 } = 3,
+    BurningShip { z0: Complex } = 4,
+    BurningShipJulia { c: Complex } = 5,
+    Tricorn { z0: Complex } = 6,
+    TricornJulia { c: Complex } = 7,
+    Celtic { z0: Complex } = 8,
+    CelticJulia { c: Complex } = 9,
+    Buffalo { z0: Complex } = 10,
+    BuffaloJulia { c: Complex } = 11,
+    Perpendicular { z0: Complex } = 12,
+    PerpendicularJulia { c: Complex } = 13,
 }
 impl FractalType {
-    pub(crate) fn new_mandelbrot(z0: Complex) -> Self {
-        Self::Mandelbrot { z0 }
+    pub(crate) const fn new_mandelbrot(z0: Complex, exponent: f32) -> Self {
+        Self::Mandelbrot { z0, exponent }
     }
 
-    pub(crate) fn new_metabrot(sub_fractal_width: u32) -> Self {
+    pub(crate) const fn new_metabrot(sub_fractal_width: u32) -> Self {
         Self::Metabrot { sub_fractal_width }
     }
 
-    pub(crate) fn new_julia(c: Complex) -> Self {
-        Self::JuliaSet { c }
+    pub(crate) const fn new_julia(c: Complex, exponent: f32) -> Self {
+        Self::JuliaSet { c, exponent }
     }
 
-    pub(crate) fn new_meta_julia(sub_fractal_width: u32) -> Self {
+    pub(crate) const fn new_meta_julia(sub_fractal_width: u32) -> Self {
         Self::MetaJulia { sub_fractal_width }
     }
+
+    pub(crate) const fn new_burning_ship(z0: Complex) -> Self {
+        Self::BurningShip { z0 }
+    }
+
+    pub(crate) const fn new_burning_ship_julia(c: Complex) -> Self {
+        Self::BurningShipJulia { c }
+    }
+
+    pub(crate) const fn new_tricorn(z0: Complex) -> Self {
+        Self::Tricorn { z0 }
+    }
+
+    pub(crate) const fn new_tricorn_julia(c: Complex) -> Self {
+        Self::TricornJulia { c }
+    }
+
+    pub(crate) const fn new_celtic(z0: Complex) -> Self {
+        Self::Celtic { z0 }
+    }
+
+    pub(crate) const fn new_celtic_julia(c: Complex) -> Self {
+        Self::CelticJulia { c }
+    }
+
+    pub(crate) const fn new_buffalo(z0: Complex) -> Self {
+        Self::Buffalo { z0 }
+    }
+
+    pub(crate) const fn new_buffalo_julia(c: Complex) -> Self {
+        Self::BuffaloJulia { c }
+    }
+
+    pub(crate) const fn new_perpendicular(z0: Complex) -> Self {
+        Self::Perpendicular { z0 }
+    }
+
+    pub(crate) const fn new_perpendicular_julia(c: Complex) -> Self {
+        Self::PerpendicularJulia { c }
+    }
+
+    /// the fractals that can be added from the global window,
+    /// each parameter plane next to its julia set
+    pub(crate) const PAIRS: [[Self; 2]; 7] = [
+        [
+            Self::new_mandelbrot(Complex::ZERO, 2.0),
+            Self::new_julia(Complex::ZERO, 2.0),
+        ],
+        [Self::new_metabrot(8), Self::new_meta_julia(8)],
+        [
+            Self::new_burning_ship(Complex::ZERO),
+            Self::new_burning_ship_julia(Complex::ZERO),
+        ],
+        [
+            Self::new_tricorn(Complex::ZERO),
+            Self::new_tricorn_julia(Complex::ZERO),
+        ],
+        [
+            Self::new_celtic(Complex::ZERO),
+            Self::new_celtic_julia(Complex::ZERO),
+        ],
+        [
+            Self::new_buffalo(Complex::ZERO),
+            Self::new_buffalo_julia(Complex::ZERO),
+        ],
+        [
+            Self::new_perpendicular(Complex::ZERO),
+            Self::new_perpendicular_julia(Complex::ZERO),
+        ],
+    ];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Mandelbrot { .. } => "mandelbrot",
+            Self::Metabrot { .. } => "metabrot",
+            Self::JuliaSet { .. } => "julia set",
+            Self::MetaJulia { .. } => "metajulia",
+            Self::BurningShip { .. } => "burning ship",
+            Self::BurningShipJulia { .. } => "burning ship julia",
+            Self::Tricorn { .. } => "tricorn",
+            Self::TricornJulia { .. } => "tricorn julia",
+            Self::Celtic { .. } => "celtic",
+            Self::CelticJulia { .. } => "celtic julia",
+            Self::Buffalo { .. } => "buffalo",
+            Self::BuffaloJulia { .. } => "buffalo julia",
+            Self::Perpendicular { .. } => "perpendicular",
+            Self::PerpendicularJulia { .. } => "perpendicular julia",
+        }
+    }
+
+    /// which iteration function the shader should use,
+    /// matches the FORMULA_* constants in the shader
+    fn formula(&self) -> u32 {
+        match self {
+            Self::Mandelbrot { .. }
+            | Self::Metabrot { .. }
+            | Self::JuliaSet { .. }
+            | Self::MetaJulia { .. } => 0,
+            Self::BurningShip { .. } | Self::BurningShipJulia { .. } => 1,
+            Self::Tricorn { .. } | Self::TricornJulia { .. } => 2,
+            Self::Celtic { .. } | Self::CelticJulia { .. } => 3,
+            Self::Buffalo { .. } | Self::BuffaloJulia { .. } => 4,
+            Self::Perpendicular { .. } | Self::PerpendicularJulia { .. } => 5,
+        }
+    }
+
+    /// whether the shader can render this fractal at the precision.
+    /// only the plain iteration handles the meta-fractals and other exponents,
+    /// and the reference orbit and the perturbed iteration are only written for z^2 + c.
+    fn supports_precision(&self, precision: Precision) -> bool {
+        match precision {
+            Precision::Single => true,
+            Precision::DoubleSingle | Precision::Double => {
+                !matches!(self, Self::Metabrot { .. } | Self::MetaJulia { .. })
+                    && self.exponent() == 2.0
+            }
+            Precision::Perturbation => {
+                matches!(self, Self::Mandelbrot { .. } | Self::JuliaSet { .. })
+                    && self.exponent() == 2.0
+            }
+        }
+    }
+
+    /// whether the iteration pass keeps each pixel's resume at the precision,
+    /// which is just the plain iteration since the meta-fractals iterate many orbits per pixel
+    fn supports_resume(&self, precision: Precision) -> bool {
+        precision == Precision::Single
+            && !matches!(self, Self::Metabrot { .. } | Self::MetaJulia { .. })
+    }
+
+    /// the power we raise z to each iteration
+    fn exponent(&self) -> f32 {
+        match self {
+            Self::Mandelbrot { exponent, .. } | Self::JuliaSet { exponent, .. } => *exponent,
+            _ => 2.0,
+        }
+    }
 }
+/// what the shader does its arithmetic in, from fastest to deepest
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Precision {
+    Single = 0,
+    /// two f32s per number, about 48 bits
+    DoubleSingle = 1,
+    /// native f64, only if the adapter supports SHADER_F64
+    Double = 2,
+    /// f32 offsets from a full precision reference orbit
+    Perturbation = 3,
+}
+impl Precision {
+    const ALL: [Self; 4] = [
+        Self::Single,
+        Self::DoubleSingle,
+        Self::Double,
+        Self::Perturbation,
+    ];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Single => "single",
+            Self::DoubleSingle => "double-single",
+            Self::Double => "double",
+            Self::Perturbation => "perturbation",
+        }
+    }
+
+    /// roughly the smallest difference the precision can tell apart near 1.
+    /// once a pixel is around this big the image falls apart into blocks.
+    fn epsilon(&self) -> f64 {
+        match self {
+            Self::Single => f32::EPSILON as f64,
+            // not quite 48 bits since the lo part loses a few to rounding
+            Self::DoubleSingle => 2.0f64.powi(-44),
+            Self::Double => f64::EPSILON,
+            // this only runs out when the f64 radius does
+            Self::Perturbation => MIN_RADIUS,
+        }
+    }
+}
+
+/// how max_depth gets picked
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum DepthMode {
+    /// just the slider
+    Manual,
+    /// doubles max_depth every frame until the extra depth doesn't change any pixels
+    KeepIterating,
+    /// doubles and halves max_depth to keep up with how late the pixels escape
+    Auto,
+}
+impl DepthMode {
+    const ALL: [Self; 3] = [Self::Manual, Self::KeepIterating, Self::Auto];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::KeepIterating => "keep iterating",
+            Self::Auto => "auto",
+        }
+    }
+}
+
 // impl Default for FractalType {
 //     fn default() -> Self {
 //         Self::Mandelbrot { z0: Complex::ZERO }
@@ -42,90 +293,480 @@ impl FractalType {
 
 #[repr(C)]
 #[derive(Clone, Copy, bytemuck::NoUninit)]
-struct Params {
+pub(crate) struct Params {
     // view params
     center_real: f32,
     center_imag: f32,
     radius_real: f32,
     radius_imag: f32,
+    // in pixels, for finding a pixel's orbit
+    width: u32,
 
     // shared params
     max_depth: u32,
     escape_radius_2: f32,
 
     // specialized params
+    // which plane we're drawing, the abs variants reuse the mandelbrot and julia planes
     fractal_type: u32,
+    // which function we iterate
+    formula: u32,
+    // z -> z^exponent + c, only used by the mandelbrot and julia formula
+    exponent: f32,
     // either z0 or c depending on fractal_type
     point_real: f32,
     point_imag: f32,
     // used in the the meta-fractals
     sub_fractal_width: u32,
+
+    // precision params
+    precision_mode: u32,
+    // the rest of the center that didn't fit in center_real and center_imag,
+    // for the double-single and double precisions
+    center_real_lo: f32,
+    center_imag_lo: f32,
+    center_real_lo2: f32,
+    center_imag_lo2: f32,
+    point_real_lo: f32,
+    point_imag_lo: f32,
+    // with perturbation the view params are relative to the reference orbit
+    // and in units of 2^radius_exponent
+    radius_exponent: i32,
+    reference_orbit_len: u32,
+
+    // panning params
+    reuse_previous: u32,
+    shift_x: i32,
+    shift_y: i32,
+
+    // antialiasing params
+    jitter_x: f32,
+    jitter_y: f32,
+    sample_count: u32,
+
+    // incremental depth params
+    resume_previous: u32,
+    changed_depth: u32,
+
+    // time slicing params
+    slice_start: u32,
+    slice_end: u32,
+
+    // coloring params
+    cycle_mode: u32,
+    color_function: u32,
+    phase: f32,
+    density: f32,
+    distance_mode: u32,
+    distance_thickness: f32,
+    interior_mode: u32,
+
+    // orbit trap params
+    trap_shape: u32,
+    trap_point_real: f32,
+    trap_point_imag: f32,
+    trap_direction_real: f32,
+    trap_direction_imag: f32,
+    trap_radius: f32,
+
+    // averaging params
+    average_mode: u32,
+    stripe_density: f32,
+
+    // decomposition params
+    decomposition_mode: u32,
+    decomposition_order: u32,
+    decomposition_strength: f32,
+    texture_mode: u32,
+
+    // lighting params
+    lighting_mode: u32,
+    light_x: f32,
+    light_y: f32,
+    light_z: f32,
+    specular: f32,
+    relief: f32,
 }
 impl Params {
+    /// reference must be Some for the perturbation precision
+    #[allow(clippy::too_many_arguments)]
     fn new(
-        camera: Camera,
+        camera: &Camera,
         width: u32,
         height: u32,
         ty: FractalType,
         max_depth: u32,
         escape_radius: f32,
+        precision: Precision,
+        reference: Option<&Reference>,
+        coloring: &Coloring,
+        trap: &OrbitTrap,
+        shift: Option<[i32; 2]>,
+        jitter: [f32; 2],
+        sample_count: u32,
+        resume: bool,
+        changed_depth: u32,
+        slice: std::ops::Range<u32>,
     ) -> Self {
-        // TODO: do this in a better way
-        match ty {
-            FractalType::Mandelbrot { z0 } => Self {
-                center_real: camera.center.real,
-                center_imag: camera.center.imag,
-                radius_real: camera.radius_real,
-                radius_imag: camera.radius_real * height as f32 / width as f32,
-                max_depth,
-                escape_radius_2: escape_radius * escape_radius,
-                fractal_type: 0,
-                point_real: z0.real,
-                point_imag: z0.imag,
-                sub_fractal_width: 0,
-            },
-            FractalType::Metabrot { sub_fractal_width } => Self {
-                center_real: camera.center.real,
-                center_imag: camera.center.imag,
-                radius_real: camera.radius_real,
-                radius_imag: camera.radius_real * height as f32 / width as f32,
-                max_depth,
-                escape_radius_2: escape_radius * escape_radius,
-                fractal_type: 1,
-                point_real: 0.0,
-                point_imag: 0.0,
-                sub_fractal_width,
-            },
-            FractalType::JuliaSet { c } => Self {
-                center_real: camera.center.real,
-                center_imag: camera.center.imag,
-                radius_real: camera.radius_real,
-                radius_imag: camera.radius_real * height as f32 / width as f32,
-                max_depth,
-                escape_radius_2: escape_radius * escape_radius,
-                fractal_type: 2,
-                point_real: c.real,
-                point_imag: c.imag,
-                sub_fractal_width: 0,
+        let (fractal_type, point, sub_fractal_width) = match ty {
+            FractalType::Mandelbrot { z0, .. }
+            | FractalType::BurningShip { z0 }
+            | FractalType::Tricorn { z0 }
+            | FractalType::Celtic { z0 }
+            | FractalType::Buffalo { z0 }
+            | FractalType::Perpendicular { z0 } => (0, z0, 0),
+            FractalType::Metabrot { sub_fractal_width } => (1, Complex::ZERO, sub_fractal_width),
+            FractalType::JuliaSet { c, .. }
+            | FractalType::BurningShipJulia { c }
+            | FractalType::TricornJulia { c }
+            | FractalType::CelticJulia { c }
+            | FractalType::BuffaloJulia { c }
+            | FractalType::PerpendicularJulia { c } => (2, c, 0),
+            FractalType::MetaJulia { sub_fractal_width } => (3, Complex::ZERO, sub_fractal_width),
+        };
+        let exponent = ty.exponent();
+        // orbits of z^p + c are guaranteed to escape past 2^(1/(p-1)) instead of 2,
+        // so scale the escape radius by that so it means the same thing for every degree.
+        // for p <= 1 there's no radius past which orbits are sure to escape,
+        // z^p doesn't outgrow z and below 0 it lands back around c,
+        // so the escape radius is just where we stop and we take it as it is.
+        let escape_radius = if precision == Precision::Perturbation {
+            // only z^2 + c, where the scaling below does nothing
+            escape_radius.min(MAX_PERTURBATION_ESCAPE_RADIUS)
+        } else if exponent > 1.0 {
+            (escape_radius * 0.5 * 2.0f32.powf(1.0 / (exponent - 1.0))).min(MAX_ESCAPE_RADIUS)
+        } else {
+            escape_radius
+        };
+        let (point_real, point_real_lo) = split_f64(point.real);
+        let (point_imag, point_imag_lo) = split_f64(point.imag);
+        let (center_real, center_real_lo, center_real_lo2) = camera.center.real.to_f32_triple();
+        let (center_imag, center_imag_lo, center_imag_lo2) = camera.center.imag.to_f32_triple();
+        let (center, radius_real, radius_exponent, reference_orbit_len) = match reference {
+            Some(reference) if precision == Precision::Perturbation => {
+                let (radius_mantissa, radius_exponent) = split_exponent(camera.radius_real);
+                let scale = 2.0f64.powi(-radius_exponent);
+                let offset = Complex {
+                    real: (&camera.center.real - &reference.point.real).to_f64() * scale,
+                    imag: (&camera.center.imag - &reference.point.imag).to_f64() * scale,
+                };
+                (
+                    offset,
+                    radius_mantissa,
+                    radius_exponent,
+                    reference.orbit_len,
+                )
+            }
+            _ => (
+                Complex {
+                    real: center_real as f64,
+                    imag: center_imag as f64,
+                },
+                camera.radius_real as f32,
+                0,
+                0,
+            ),
+        };
+        Self {
+            center_real: center.real as f32,
+            center_imag: center.imag as f32,
+            radius_real,
+            radius_imag: radius_real * height as f32 / width as f32,
+            width,
+            max_depth,
+            escape_radius_2: escape_radius * escape_radius,
+            fractal_type,
+            formula: ty.formula(),
+            exponent,
+            point_real,
+            point_imag,
+            sub_fractal_width,
+            precision_mode: precision as u32,
+            center_real_lo,
+            center_imag_lo,
+            center_real_lo2,
+            center_imag_lo2,
+            point_real_lo,
+            point_imag_lo,
+            radius_exponent,
+            reference_orbit_len,
+            reuse_previous: shift.is_some() as u32,
+            shift_x: shift.map_or(0, |shift| shift[0]),
+            shift_y: shift.map_or(0, |shift| shift[1]),
+            jitter_x: jitter[0],
+            jitter_y: jitter[1],
+            sample_count,
+            resume_previous: resume as u32,
+            changed_depth,
+            slice_start: slice.start,
+            slice_end: slice.end,
+            cycle_mode: coloring.cycle_mode as u32,
+            color_function: coloring.color_function as u32,
+            phase: coloring.phase,
+            density: coloring.density,
+            distance_mode: coloring.distance_mode as u32,
+            distance_thickness: coloring.distance_thickness,
+            interior_mode: coloring.interior_mode as u32,
+            trap_shape: trap.shape as u32,
+            trap_point_real: trap.point.real as f32,
+            trap_point_imag: trap.point.imag as f32,
+            trap_direction_real: trap.angle.cos(),
+            trap_direction_imag: trap.angle.sin(),
+            trap_radius: trap.radius,
+            average_mode: coloring.average_mode as u32,
+            stripe_density: coloring.stripe_density,
+            decomposition_mode: coloring.decomposition_mode as u32,
+            decomposition_order: coloring.decomposition_order,
+            decomposition_strength: coloring.decomposition_strength,
+            texture_mode: coloring.texture_mode as u32,
+            lighting_mode: coloring.lighting_mode as u32,
+            light_x: coloring.light_elevation.cos() * coloring.light_angle.cos(),
+            light_y: coloring.light_elevation.cos() * coloring.light_angle.sin(),
+            light_z: coloring.light_elevation.sin(),
+            specular: coloring.specular,
+            relief: coloring.relief,
+        }
+    }
+}
+
+/// for a render_scale
+fn interactive_quality_name(scale: u32) -> String {
+    if scale == 1 {
+        "full".to_owned()
+    } else {
+        format!("1/{scale}")
+    }
+}
+
+/// a text box for values that need to be typed or pasted exactly.
+/// shows value until it's focused, keeps what's being typed in egui's memory,
+/// and returns the parsed text once it loses focus.
+fn text_entry<T>(
+    ui: &mut egui::Ui,
+    label: &str,
+    value: impl std::fmt::Display,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Option<T> {
+    let id = ui.make_persistent_id(label);
+    let mut text = ui
+        .data_mut(|data| data.get_temp::<String>(id))
+        .unwrap_or_else(|| value.to_string());
+    let response = ui
+        .horizontal(|ui| {
+            let response = ui.text_edit_singleline(&mut text);
+            ui.label(label);
+            response
+        })
+        .inner;
+    if response.has_focus() {
+        ui.data_mut(|data| data.insert_temp(id, text));
+        None
+    } else {
+        ui.data_mut(|data| data.remove::<String>(id));
+        if response.lost_focus() {
+            parse(&text)
+        } else {
+            None
+        }
+    }
+}
+
+fn parse_f64(s: &str) -> Option<f64> {
+    s.trim().parse().ok().filter(|x: &f64| x.is_finite())
+}
+
+/// splits x into an f32 and the f32 rest, for the double-single and double precisions
+pub(crate) fn split_f64(x: f64) -> (f32, f32) {
+    let hi = x as f32;
+    (hi, (x - hi as f64) as f32)
+}
+
+/// splits x into mantissa * 2^exponent with the mantissa in [1, 2),
+/// so the shader can work with numbers too small for an f32
+fn split_exponent(x: f64) -> (f32, i32) {
+    let exponent = x.log2().floor() as i32;
+    ((x * 2.0f64.powi(-exponent)) as f32, exponent)
+}
+
+/// a high precision orbit that the shader iterates the pixels relative to when deep zooming
+#[derive(Clone)]
+struct Reference {
+    /// the c for mandelbrots and the z0 for julias
+    point: BigComplex,
+    // what the orbit was computed for
+    radius_real: f64,
+    ty: FractalType,
+    max_depth: u32,
+    escape_radius: f32,
+    /// stops short of max_depth + 1 if the reference escapes
+    orbit_len: u32,
+    /// how many times it's been moved off the center onto a glitched pixel
+    glitch_moves: u32,
+}
+impl Reference {
+    /// the reference at the center of the camera, before its orbit is computed
+    fn new(camera: &Camera, ty: FractalType, max_depth: u32, escape_radius: f32) -> Self {
+        let frac_limbs = camera.frac_limbs();
+        Self {
+            point: BigComplex {
+                real: camera.center.real.with_frac_limbs(frac_limbs),
+                imag: camera.center.imag.with_frac_limbs(frac_limbs),
             },
-            FractalType::MetaJulia { sub_fractal_width } => Self {
-                center_real: camera.center.real,
-                center_imag: camera.center.imag,
-                radius_real: camera.radius_real,
-                radius_imag: camera.radius_real * height as f32 / width as f32,
-                max_depth,
-                escape_radius_2: escape_radius * escape_radius,
-                fractal_type: 3,
-                point_real: 0.0,
-                point_imag: 0.0,
-                sub_fractal_width,
+            radius_real: camera.radius_real,
+            ty,
+            max_depth,
+            escape_radius,
+            orbit_len: 0,
+            glitch_moves: 0,
+        }
+    }
+
+    /// the same reference moved by offset from the center, for when a pixel there glitched
+    fn moved(&self, camera: &Camera, offset: [f64; 2]) -> Self {
+        let frac_limbs = self.point.frac_limbs();
+        Self {
+            point: BigComplex {
+                real: &camera.center.real.with_frac_limbs(frac_limbs)
+                    + &BigFixed::from_f64(offset[0], frac_limbs),
+                imag: &camera.center.imag.with_frac_limbs(frac_limbs)
+                    + &BigFixed::from_f64(offset[1], frac_limbs),
             },
+            orbit_len: 0,
+            glitch_moves: self.glitch_moves + 1,
+            ..self.clone()
+        }
+    }
+
+    /// computes the orbit at full precision and rounds it to f32s for the gpu.
+    /// counts the iterations in computed as it goes, and gives up once cancelled is set.
+    fn orbit(&self, computed: &AtomicU32, cancelled: &AtomicBool) -> Option<Vec<[f32; 2]>> {
+        let frac_limbs = self.point.frac_limbs();
+        let (mut z, c) = match self.ty {
+            FractalType::Mandelbrot { z0, .. } => {
+                (BigComplex::from_complex(z0, frac_limbs), self.point.clone())
+            }
+            FractalType::JuliaSet { c, .. } => {
+                (self.point.clone(), BigComplex::from_complex(c, frac_limbs))
+            }
+            _ => unreachable!("only z^2 + c supports perturbation"),
+        };
+        // the same escape radius as the shader's, but going on until it's escaped for sure
+        let escape_radius = self.escape_radius.min(MAX_PERTURBATION_ESCAPE_RADIUS) as f64;
+        let bailout_2 = (escape_radius * escape_radius).max(4.0);
+        // most references escape long before max_depth, so it only grows as far as it goes
+        let mut orbit = Vec::new();
+        for depth in 0..=self.max_depth {
+            if cancelled.load(Ordering::Relaxed) {
+                return None;
+            }
+            computed.store(depth, Ordering::Relaxed);
+            let z_complex = z.to_complex();
+            orbit.push([z_complex.real as f32, z_complex.imag as f32]);
+            let real = z.real.to_f64();
+            let imag = z.imag.to_f64();
+            if real * real + imag * imag > bailout_2 {
+                break;
+            }
+            let real2 = &z.real * &z.real;
+            let imag2 = &z.imag * &z.imag;
+            let cross = &z.real * &z.imag;
+            z = BigComplex {
+                real: &(&real2 - &imag2) + &c.real,
+                imag: &(&cross + &cross) + &c.imag,
+            };
         }
+        Some(orbit)
+    }
+
+    /// whether the shader can keep using this orbit for the view
+    fn is_valid(
+        &self,
+        camera: &Camera,
+        ty: FractalType,
+        max_depth: u32,
+        escape_radius: f32,
+    ) -> bool {
+        // once the reference is off screen the deltas get big enough that f32 struggles
+        let offset_real = (&camera.center.real - &self.point.real).to_f64();
+        let offset_imag = (&camera.center.imag - &self.point.imag).to_f64();
+        self.ty == ty
+            && self.max_depth == max_depth
+            && self.escape_radius == escape_radius
+            // past this the point wasn't stored with enough precision
+            && camera.radius_real > self.radius_real * 2.0f64.powi(-32)
+            && offset_real.abs() < camera.radius_real
+            && offset_imag.abs() < camera.radius_real
     }
 }
 
+/// a reference orbit being computed on another thread, so deep zooms don't freeze the ui
+struct ReferenceJob {
+    reference: Reference,
+    /// how far into the orbit it's got
+    computed: Arc<AtomicU32>,
+    /// set once it's dropped, so a reference for an old view stops computing
+    cancelled: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Option<Vec<[f32; 2]>>>>,
+}
+impl ReferenceJob {
+    fn spawn(reference: Reference) -> Self {
+        let computed = Arc::new(AtomicU32::new(0));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let thread = std::thread::spawn({
+            let reference = reference.clone();
+            let computed = computed.clone();
+            let cancelled = cancelled.clone();
+            move || reference.orbit(&computed, &cancelled)
+        });
+        Self {
+            reference,
+            computed,
+            cancelled,
+            thread: Some(thread),
+        }
+    }
+
+    /// the reference and its orbit, once it's done
+    fn poll(&mut self) -> Option<(Reference, Vec<[f32; 2]>)> {
+        if !self.thread.as_ref()?.is_finished() {
+            return None;
+        }
+        let orbit = self.thread.take()?.join().ok()??;
+        let reference = Reference {
+            orbit_len: orbit.len() as u32,
+            ..self.reference.clone()
+        };
+        Some((reference, orbit))
+    }
+
+    fn progress(&self) -> f32 {
+        self.computed.load(Ordering::Relaxed) as f32 / self.reference.max_depth.max(1) as f32
+    }
+}
+impl Drop for ReferenceJob {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// an iteration pass spread over several frames to stay under the frame budget.
+/// keeps what its params were made from, since every slice has to iterate the same way.
+struct IterationPass {
+    /// the rows above this are done
+    next_row: u32,
+    /// how far the view moved since previous_orbit_buffer, if it's reusing it
+    shift: Option<[i32; 2]>,
+    /// whether orbit_buffer needs copying to previous_orbit_buffer before the first slice
+    copy_previous: bool,
+    jitter: [f32; 2],
+    resume: bool,
+    changed_depth: u32,
+}
+
 pub(crate) struct FractalUiResponse {
     pub(crate) should_open_settings: bool,
+    /// whether the pointer is over it, which the scheduler renders first
+    pub(crate) hovered: bool,
     // pub(crate) new_point: Option<Complex>,
 }
 
@@ -145,40 +786,98 @@ pub(crate) struct Fractal {
     /// this should really be a (u32, u32) but egui uses floats for some reason
     size: eframe::egui::Vec2,
 
-    // // this is so that we can manually do subsampling,
-    // // which allows for reverse subsampling to render at a lower resolution
-    // render_width: u32,
-    // render_height: u32,
-    // output_width: u32,
-    // output_height: u32,
+    /// how many screen pixels across each rendered pixel covers,
+    /// which goes up to interactive_scale while moving and back down to 1 once still
+    render_scale: u32,
+    /// the render_scale to use while moving, set in the settings
+    interactive_scale: u32,
+    /// whether the camera moved this frame
+    moved: bool,
+    antialiasing: Antialiasing,
+    /// how many samples are in accumulation_buffer, which starts over whenever anything changes
+    samples: u32,
+    /// the sample offset the orbits were iterated with
+    orbits_jitter: [f32; 2],
+    depth_mode: DepthMode,
+    /// the fraction of pixels that hit max_depth in the last pass we got the stats of
+    unresolved: f32,
 
     // internal stuff
     texture_id: eframe::egui::TextureId,
+    /// whether to rerun the iteration pass, which always recolors
     needs_update: bool,
+    /// whether to rerun just the coloring pass
+    needs_recolor: bool,
+    /// whole render pixels panned since the last iteration pass, with y going down.
+    /// only those newly on screen need iterating.
+    pan_shift: [i32; 2],
+    /// the iteration pass that's partway done, while texture still shows the last finished one
+    pass: Option<IterationPass>,
+    /// whether to spread the iteration pass over several frames,
+    /// doing as much each frame as the scheduler gives it time for
+    time_slicing: bool,
+    /// how many iterations a millisecond fits going by the last timed slice,
+    /// counting every pixel as going all the way to max_depth
+    iteration_rate: f64,
+    /// the iterations in the slice the timer is reading back the time of
+    timed_cost: f64,
+    /// whether the time being read back is a benchmark frame's
+    timing_benchmark: bool,
+    /// what max_depth was before it went up since the last iteration pass.
+    /// only the pixels that hadn't escaped need iterating further if resumable.
+    deepen: Option<u32>,
+    /// whether every pixel's resume matches its orbit,
+    /// which is only after a full iteration pass with the single precision
+    resumable: bool,
 
     // render pipeline
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    renderer: Arc<eframe::egui::mutex::RwLock<eframe::egui_wgpu::Renderer>>,
+    renderer: Arc<FractalRenderer>,
     texture: wgpu::Texture,
+    /// what the fragment backend's iteration pass draws to, without writing anything,
+    /// at the size of the orbits which texture catches up to once the pass is done
+    iterate_texture: wgpu::Texture,
     shader_params_buffer: wgpu::Buffer,
+    reference_orbit_buffer: wgpu::Buffer,
+    orbit_buffer: wgpu::Buffer,
+    /// what orbit_buffer had before a pan, for the iteration pass to copy from
+    previous_orbit_buffer: wgpu::Buffer,
+    /// the sum of the antialiasing samples' colors
+    accumulation_buffer: wgpu::Buffer,
+    /// where each pixel stopped iterating, for carrying on when max_depth goes up
+    resume_buffer: wgpu::Buffer,
+    palette_texture: wgpu::Texture,
+    histogram: Histogram,
+    stats: IterationStats,
+    /// the texture mapping's image
+    image_texture: wgpu::Texture,
+    /// what image_texture was made from, to notice when the coloring loads another
+    image_texture_source: Arc<TextureImage>,
     render_bind_group: wgpu::BindGroup,
-    render_pipeline: wgpu::RenderPipeline,
+    timer: GpuTimer,
 
     // fractal stuff
     id: usize,
     ty: FractalType,
     max_depth: u32,
     escape_radius: f32,
+    /// the precision picked in the settings,
+    /// which falls back to single when the fractal doesn't support it
+    precision: Precision,
+    /// the orbit for the perturbation precision
+    reference: Option<Reference>,
+    /// the next reference, while it's computing
+    reference_job: Option<ReferenceJob>,
+    coloring: Coloring,
+    trap: OrbitTrap,
+    /// how the iteration pass runs
+    backend: Backend,
+    /// overrides backend while it's running, and keeps its results after
+    benchmark: Option<Benchmark>,
 }
 impl Fractal {
-    pub(crate) fn default(
-        render_state: &eframe::egui_wgpu::RenderState,
-        id: usize,
-        ty: FractalType,
-    ) -> Self {
+    pub(crate) fn default(renderer: &Arc<FractalRenderer>, id: usize, ty: FractalType) -> Self {
         Self::new(
-            render_state,
+            renderer,
             Camera::default(),
             // Camera {
             //     center: Complex::zero(),
@@ -194,8 +893,9 @@ impl Fractal {
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
-        render_state: &eframe::egui_wgpu::RenderState,
+        renderer: &Arc<FractalRenderer>,
         camera: Camera,
         velocity: eframe::egui::Vec2,
         size: eframe::egui::Vec2,
@@ -204,11 +904,8 @@ impl Fractal {
         max_depth: u32,
         escape_radius: f32,
     ) -> Self {
-        let device = render_state.device.clone();
-        let queue = render_state.queue.clone();
-        let renderer = render_state.renderer.clone();
-
-        let shader_module = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
+        let device = &renderer.device;
+        let queue = &renderer.queue;
 
         let shader_params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("shader_params_buffer"),
@@ -220,6 +917,32 @@ impl Fractal {
                 | wgpu::BufferUsages::COPY_DST,
         });
 
+        // the real one is made once we're deep zooming, but the bind group needs something
+        let reference_orbit_buffer = Self::create_reference_orbit_buffer(device, 1);
+        let orbit_buffer = Self::create_orbit_buffer(device, "orbit_buffer", size);
+        let previous_orbit_buffer =
+            Self::create_orbit_buffer(device, "previous_orbit_buffer", size);
+        let accumulation_buffer = Self::create_accumulation_buffer(device, size);
+        let resume_buffer = Self::create_resume_buffer(device, 1);
+        // filled in on every render since it's tiny
+        let palette_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("palette_texture"),
+            size: wgpu::Extent3d {
+                width: PALETTE_SIZE as u32,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D1,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let coloring = Coloring::default();
+        let image_texture_source = coloring.texture.clone();
+        let image_texture = Self::create_image_texture(device, queue, &image_texture_source);
+
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("texture"),
             size: wgpu::Extent3d {
@@ -230,100 +953,39 @@ impl Fractal {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            format: TEXTURE_FORMAT,
             usage: wgpu::TextureUsages::TEXTURE_BINDING
                 | wgpu::TextureUsages::RENDER_ATTACHMENT
                 | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[wgpu::TextureFormat::Rgba8UnormSrgb],
+            view_formats: &[TEXTURE_FORMAT],
         });
 
-        let texture_id = renderer.write().register_native_texture(
-            &device,
+        let iterate_texture = Self::create_iterate_texture(device, texture.size());
+
+        let texture_id = renderer.egui_renderer.write().register_native_texture(
+            device,
             &texture.create_view(&wgpu::TextureViewDescriptor::default()),
             wgpu::FilterMode::Nearest,
         );
 
-        let render_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("render_bind_group_layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: Some(
-                            wgpu::BufferSize::new(shader_params_buffer.size()).unwrap(),
-                        ),
-                    },
-                    count: None,
-                }],
-            });
-        let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("render_bind_group"),
-            layout: &render_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: shader_params_buffer.as_entire_binding(),
-            }],
-        });
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("render_pipeline"),
-            layout: Some(
-                &device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: Some("render_pipeline_layout"),
-                    bind_group_layouts: &[&render_bind_group_layout],
-                    push_constant_ranges: &[],
-                }),
-            ),
-            vertex: wgpu::VertexState {
-                module: &shader_module,
-                entry_point: None,
-                compilation_options: Default::default(),
-                buffers: &[
-                    // // @location(0) vertex_pos: vec2<f32>,
-                    // wgpu::VertexBufferLayout {
-                    //     array_stride: 4 * 2,
-                    //     step_mode: wgpu::VertexStepMode::Vertex,
-                    //     attributes: &wgpu::vertex_attr_array![0 => Float32x2],
-                    // },
-                    // // @location(1) particle_pos: vec2<f32>,
-                    // wgpu::VertexBufferLayout {
-                    //     array_stride: 4 * 2,
-                    //     step_mode: wgpu::VertexStepMode::Instance,
-                    //     attributes: &wgpu::vertex_attr_array![1 => Float32x2],
-                    // },
-                    // // @location(2) particle_vel: vec2<f32>,
-                    // wgpu::VertexBufferLayout {
-                    //     array_stride: 4 * 2,
-                    //     step_mode: wgpu::VertexStepMode::Instance,
-                    //     attributes: &wgpu::vertex_attr_array![2 => Float32x2],
-                    // },
-                    // // @location(3) particle_species: u32,
-                    // wgpu::VertexBufferLayout {
-                    //     array_stride: 4,
-                    //     step_mode: wgpu::VertexStepMode::Instance,
-                    //     attributes: &wgpu::vertex_attr_array![3 => Uint32],
-                    // },
-                ],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader_module,
-                entry_point: None,
-                compilation_options: Default::default(),
-                // targets: &[Some(config.view_formats[0].into())],
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: texture.format(),
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::all(),
-                })],
-            }),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-            cache: None,
-        });
+        let histogram = Histogram::new(device);
+        let stats = IterationStats::new(device);
+        let timer = GpuTimer::new(device);
+
+        let render_bind_group = Self::create_render_bind_group(
+            device,
+            &renderer.bind_group_layout,
+            &shader_params_buffer,
+            &reference_orbit_buffer,
+            &orbit_buffer,
+            &previous_orbit_buffer,
+            &accumulation_buffer,
+            &resume_buffer,
+            &palette_texture,
+            &histogram,
+            &stats,
+            &image_texture,
+        );
 
         Self {
             camera,
@@ -331,22 +993,251 @@ impl Fractal {
             size,
             texture_id,
             needs_update: true,
-            device,
-            queue,
-            renderer,
+            needs_recolor: true,
+            pan_shift: [0, 0],
+            pass: None,
+            time_slicing: true,
+            iteration_rate: INITIAL_ITERATION_RATE,
+            timed_cost: 0.0,
+            timing_benchmark: false,
+            deepen: None,
+            resumable: false,
+            render_scale: 1,
+            interactive_scale: 2,
+            moved: false,
+            antialiasing: Antialiasing::Off,
+            samples: 0,
+            orbits_jitter: [0.0, 0.0],
+            depth_mode: DepthMode::Manual,
+            unresolved: 0.0,
+            renderer: renderer.clone(),
             texture,
+            iterate_texture,
             shader_params_buffer,
+            reference_orbit_buffer,
+            orbit_buffer,
+            previous_orbit_buffer,
+            accumulation_buffer,
+            resume_buffer,
+            palette_texture,
+            histogram,
+            stats,
+            image_texture,
+            image_texture_source,
             render_bind_group,
-            render_pipeline,
+            timer,
             id,
             ty,
             max_depth,
             escape_radius,
+            precision: Precision::Single,
+            reference: None,
+            reference_job: None,
+            coloring,
+            trap: OrbitTrap::default(),
+            backend: Backend::Fragment,
+            benchmark: None,
         }
     }
 
-    pub(crate) fn camera(&self) -> Camera {
-        self.camera
+    fn create_reference_orbit_buffer(device: &wgpu::Device, len: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("reference_orbit_buffer"),
+            size: (len * std::mem::size_of::<[f32; 2]>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_orbit_buffer(device: &wgpu::Device, label: &str, size: egui::Vec2) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: size.x as u64 * size.y as u64 * ORBIT_SIZE,
+            // copied into previous_orbit_buffer before a pan
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_image_texture(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &TextureImage,
+    ) -> wgpu::Texture {
+        device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("image_texture"),
+                size: wgpu::Extent3d {
+                    width: image.width,
+                    height: image.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            bytemuck::cast_slice(&image.texels),
+        )
+    }
+
+    fn create_accumulation_buffer(device: &wgpu::Device, size: egui::Vec2) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("accumulation_buffer"),
+            size: size.x as u64 * size.y as u64 * std::mem::size_of::<[f32; 4]>() as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_iterate_texture(device: &wgpu::Device, size: wgpu::Extent3d) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("iterate_texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TEXTURE_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+    }
+
+    fn create_resume_buffer(device: &wgpu::Device, pixels: u64) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("resume_buffer"),
+            size: pixels * RESUME_SIZE,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_render_bind_group(
+        device: &wgpu::Device,
+        render_bind_group_layout: &wgpu::BindGroupLayout,
+        shader_params_buffer: &wgpu::Buffer,
+        reference_orbit_buffer: &wgpu::Buffer,
+        orbit_buffer: &wgpu::Buffer,
+        previous_orbit_buffer: &wgpu::Buffer,
+        accumulation_buffer: &wgpu::Buffer,
+        resume_buffer: &wgpu::Buffer,
+        palette_texture: &wgpu::Texture,
+        histogram: &Histogram,
+        stats: &IterationStats,
+        image_texture: &wgpu::Texture,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("render_bind_group"),
+            layout: render_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: shader_params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: reference_orbit_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: orbit_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(
+                        &palette_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: histogram.readback.buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: histogram.cdf_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: wgpu::BindingResource::TextureView(
+                        &image_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: previous_orbit_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 8,
+                    resource: accumulation_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 9,
+                    resource: resume_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 10,
+                    resource: stats.readback.buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    pub(crate) fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    fn supports_precision(&self, precision: Precision) -> bool {
+        self.ty.supports_precision(precision)
+            && match precision {
+                Precision::DoubleSingle => self.renderer.double_single_works,
+                Precision::Double => self
+                    .renderer
+                    .device
+                    .features()
+                    .contains(wgpu::Features::SHADER_F64),
+                _ => true,
+            }
+    }
+
+    /// the precision we're actually rendering at
+    pub(crate) fn active_precision(&self) -> Precision {
+        if self.supports_precision(self.precision) {
+            self.precision
+        } else {
+            Precision::Single
+        }
+    }
+
+    /// how many antialiasing samples are in the image, and how many it's going for
+    pub(crate) fn samples(&self) -> (u32, u32) {
+        (self.samples, self.antialiasing.sample_count())
+    }
+
+    /// whether the pixels are getting close to what the active precision can tell apart
+    pub(crate) fn is_near_precision_limit(&self) -> bool {
+        self.camera.radius_real / (self.size.x as f64) < 16.0 * self.active_precision().epsilon()
+    }
+
+    /// the smallest render_scale whose per pixel buffers fit in the device's limits,
+    /// which big windows can need on devices that can't bind much
+    fn min_render_scale(&self) -> u32 {
+        let limits = self.renderer.device.limits();
+        let max_size = (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size);
+        let mut pixel_size = ORBIT_SIZE.max(std::mem::size_of::<[f32; 4]>() as u64);
+        if self.ty.supports_resume(self.active_precision()) {
+            pixel_size = pixel_size.max(RESUME_SIZE);
+        }
+        let [width, height] = [self.size.x as u64, self.size.y as u64];
+        (1..)
+            .find(|&scale| width.div_ceil(scale) * height.div_ceil(scale) * pixel_size <= max_size)
+            .expect("a big enough scale leaves a single pixel") as u32
     }
 
     fn texture_id(&self) -> eframe::egui::TextureId {
@@ -354,16 +1245,7 @@ impl Fractal {
     }
 
     pub(crate) fn name(&self) -> String {
-        format!(
-            "{} {}",
-            match self.ty {
-                FractalType::Mandelbrot { .. } => "mandelbrot",
-                FractalType::Metabrot { .. } => "metabrot",
-                FractalType::JuliaSet { .. } => "julia set",
-                FractalType::MetaJulia { .. } => "metajulia",
-            },
-            self.id
-        )
+        format!("{} {}", self.ty.name(), self.id)
     }
 
     pub(crate) fn pan(&mut self, pan: eframe::egui::Vec2) {
@@ -373,9 +1255,24 @@ impl Fractal {
         if pan.x == 0.0 && pan.y == 0.0 {
             return;
         }
-        self.camera.center.real -= 2.0 * pan.x / self.size.x * self.camera.radius_real;
-        self.camera.center.imag += 2.0 * pan.y * (self.camera.radius_real / self.size.x);
-        self.needs_update = true;
+        self.moved = true;
+        self.camera.pan_remainder += pan;
+        self.apply_pan_remainder();
+    }
+
+    /// only moves by whole render pixels, so the orbits still on screen can be reused
+    fn apply_pan_remainder(&mut self) {
+        let render_scale = self.render_scale as f32;
+        let whole = (self.camera.pan_remainder / render_scale).round() * render_scale;
+        if whole == egui::Vec2::ZERO {
+            return;
+        }
+        self.camera.pan_remainder -= whole;
+        let scale = self.camera.radius_real / self.size.x as f64;
+        self.camera
+            .translate(-2.0 * whole.x as f64 * scale, 2.0 * whole.y as f64 * scale);
+        self.pan_shift[0] += (whole.x / render_scale) as i32;
+        self.pan_shift[1] += (whole.y / render_scale) as i32;
     }
 
     pub(crate) fn pan_velocity(&mut self, pan_velocity: eframe::egui::Vec2) {
@@ -397,8 +1294,10 @@ impl Fractal {
         }
 
         self.pan(-mouse);
-        self.camera.radius_real /= zoom;
+        self.camera.radius_real =
+            (self.camera.radius_real / zoom as f64).clamp(MIN_RADIUS, MAX_RADIUS);
         self.pan(mouse);
+        self.moved = true;
         self.needs_update = true;
     }
 
@@ -417,6 +1316,14 @@ impl Fractal {
     //     }
     // }
 
+    /// moves the orbit trap to the point if it's following it
+    pub(crate) fn follow_point(&mut self, point: Complex) {
+        if self.trap.follow_point && self.trap.point != point {
+            self.trap.point = point;
+            self.needs_update = true;
+        }
+    }
+
     /// fills the entire ui rect with the image.
     /// draws the point if it is Some.
     /// returns whether the settings ui should be open ie we were double clicked.
@@ -442,20 +1349,20 @@ impl Fractal {
             self.autopan(dt);
         }
         // if r.hover_pos()
-        if r.contains_pointer() {
-            if let Some(mouse_pos) = ctx.input(|i| i.pointer.latest_pos()) {
-                self.zoom(
-                    mouse_pos - rect.center(),
-                    ctx.input(|i| {
-                        // if self.trackpad {
-                        //     i.zoom_delta()
-                        // } else {
-                        //     (i.smooth_scroll_delta.y / 300.0).exp()
-                        // }
-                        (i.smooth_scroll_delta.y / 300.0).exp()
-                    }),
-                )
-            }
+        if r.contains_pointer()
+            && let Some(mouse_pos) = ctx.input(|i| i.pointer.latest_pos())
+        {
+            self.zoom(
+                mouse_pos - rect.center(),
+                ctx.input(|i| {
+                    // if self.trackpad {
+                    //     i.zoom_delta()
+                    // } else {
+                    //     (i.smooth_scroll_delta.y / 300.0).exp()
+                    // }
+                    (i.smooth_scroll_delta.y / 300.0).exp()
+                }),
+            )
         }
 
         // rendering stuff
@@ -463,20 +1370,53 @@ impl Fractal {
             self.size = rect.size();
             self.needs_update = true;
         }
-        self.render_to_texture();
+        // drop the resolution while moving, then refine a step at a time once still,
+        // waiting for each step's pass to finish so it gets shown
+        let render_scale =
+            if std::mem::take(&mut self.moved) || self.velocity != egui::Vec2::ZERO {
+                self.interactive_scale
+            } else if self.pass.is_none() {
+                (self.render_scale / 2).max(1)
+            } else {
+                self.render_scale
+            }
+            .max(self.min_render_scale());
+        if render_scale != self.render_scale {
+            self.render_scale = render_scale;
+            self.apply_pan_remainder();
+            self.needs_update = true;
+        }
+        // the scheduler renders to texture once every fractal's had its ui, before egui paints
         eframe::egui::widgets::Image::from_texture(eframe::egui::load::SizedTexture::new(
             self.texture_id(),
             eframe::egui::Vec2::new(1.0, 1.0), // arbitrary size
         ))
         .paint_at(ui, rect);
+        if let Some(progress) = self.progress() {
+            ui.painter_at(rect).rect_filled(
+                egui::Rect::from_min_size(
+                    rect.left_top(),
+                    egui::Vec2::new(rect.width() * progress, 3.0),
+                ),
+                0.0,
+                ui.visuals().selection.bg_fill,
+            );
+        }
+        // autopan keeps moving without any input
+        if self.velocity != egui::Vec2::ZERO {
+            ctx.request_repaint();
+        }
         if let Some(point) = point {
+            // in f64 so it doesn't blow up when deep zooming
+            let offset_real =
+                (point.real - self.camera.center.real.to_f64()) / self.camera.radius_real;
+            let offset_imag =
+                (point.imag - self.camera.center.imag.to_f64()) / self.camera.radius_real;
             ui.painter_at(rect).circle_filled(
                 rect.center()
                     + eframe::egui::Vec2::new(
-                        0.5 * (point.real - self.camera.center.real) / self.camera.radius_real
-                            * rect.width(),
-                        -0.5 * (point.imag - self.camera.center.imag) / self.camera.radius_real
-                            * rect.width(),
+                        0.5 * offset_real as f32 * rect.width(),
+                        -0.5 * offset_imag as f32 * rect.width(),
                     ),
                 5.0,
                 eframe::egui::Color32::RED,
@@ -505,6 +1445,7 @@ impl Fractal {
 
         FractalUiResponse {
             should_open_settings: r.double_clicked(),
+            hovered: r.contains_pointer(),
             // new_point: point,
         }
     }
@@ -513,7 +1454,7 @@ impl Fractal {
     pub(crate) fn settings_ui(
         &mut self,
         ctx: &egui::Context,
-        ui: &mut eframe::egui::Ui,
+        _ui: &mut eframe::egui::Ui,
     ) -> SettingsUiResponse {
         let mut open = true;
         let mut swap_main = false;
@@ -523,30 +1464,156 @@ impl Fractal {
             .show(ctx, |ui| {
                 swap_main = ui.button("swap main").clicked();
 
+                // text boxes so exact locations can be pasted in.
+                // anything past MAX_CENTER wouldn't leave room to pan.
+                let frac_limbs = self.camera.frac_limbs();
+                let parse_center = |s: &str| {
+                    BigFixed::from_decimal(s, frac_limbs).filter(|x| x.to_f64().abs() <= MAX_CENTER)
+                };
+                if let Some(real) =
+                    text_entry(ui, "center real", &self.camera.center.real, parse_center)
+                {
+                    self.camera.center.real = real;
+                    self.needs_update = true;
+                }
+                if let Some(imag) =
+                    text_entry(ui, "center imag", &self.camera.center.imag, parse_center)
+                {
+                    self.camera.center.imag = imag;
+                    self.needs_update = true;
+                }
+                if let Some(radius) = text_entry(ui, "radius", self.camera.radius_real, |s| {
+                    s.trim()
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|radius| (MIN_RADIUS..=MAX_RADIUS).contains(radius))
+                }) {
+                    self.camera.radius_real = radius;
+                    self.needs_update = true;
+                }
+
                 let mut max_depth = self.max_depth;
                 ui.add(
-                    egui::Slider::new(&mut max_depth, 1..=1024)
+                    egui::Slider::new(&mut max_depth, 1..=MAX_DEPTH)
+                        .logarithmic(true)
                         .text("max depth")
                         .clamping(egui::SliderClamping::Never),
                 );
                 if max_depth != self.max_depth {
+                    // going deeper can carry on from where the pixels stopped
+                    if max_depth > self.max_depth {
+                        self.deepen.get_or_insert(self.max_depth);
+                    } else {
+                        self.needs_update = true;
+                    }
                     self.max_depth = max_depth;
-                    self.needs_update = true;
+                }
+                let old_depth_mode = self.depth_mode;
+                egui::ComboBox::from_label("depth mode")
+                    .selected_text(self.depth_mode.name())
+                    .show_ui(ui, |ui| {
+                        for option in DepthMode::ALL {
+                            ui.selectable_value(&mut self.depth_mode, option, option.name());
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "keep iterating doubles max depth every frame until the image stops \
+                        changing, and auto follows how late the pixels escape as you zoom",
+                    );
+                if self.depth_mode == DepthMode::KeepIterating
+                    && old_depth_mode != DepthMode::KeepIterating
+                {
+                    self.raise_max_depth();
                 }
 
                 let mut escape_radius = self.escape_radius;
-                ui.add(
+                let response = ui.add(
                     egui::Slider::new(&mut escape_radius, 0.0..=10.0)
                         .text("escape radius")
                         .clamping(egui::SliderClamping::Never),
                 );
+                if self.active_precision() == Precision::Perturbation {
+                    escape_radius = escape_radius.min(MAX_PERTURBATION_ESCAPE_RADIUS);
+                    response.on_hover_text(format!(
+                        "perturbation goes up to {MAX_PERTURBATION_ESCAPE_RADIUS}"
+                    ));
+                }
                 if escape_radius != self.escape_radius {
                     self.escape_radius = escape_radius;
                     self.needs_update = true;
                 }
+                if let FractalType::Mandelbrot { exponent, .. }
+                | FractalType::JuliaSet { exponent, .. } = &mut self.ty
+                {
+                    let mut new_exponent = *exponent;
+                    ui.add(
+                        egui::Slider::new(&mut new_exponent, -8.0..=8.0)
+                            .text("exponent")
+                            .clamping(egui::SliderClamping::Never),
+                    )
+                    .on_hover_text(
+                        "between -1 and 1 nothing grows fast enough to smooth the bands",
+                    );
+                    if new_exponent != *exponent {
+                        *exponent = new_exponent;
+                        self.needs_update = true;
+                    }
+                }
+                let mut precision = self.precision;
+                egui::ComboBox::from_label("precision")
+                    .selected_text(precision.name())
+                    .show_ui(ui, |ui| {
+                        for option in Precision::ALL {
+                            ui.add_enabled_ui(self.supports_precision(option), |ui| {
+                                let response =
+                                    ui.selectable_value(&mut precision, option, option.name());
+                                if option == Precision::DoubleSingle
+                                    && !self.renderer.double_single_works
+                                {
+                                    response.on_disabled_hover_text(
+                                        "this gpu's shader compiler got the self-test wrong",
+                                    );
+                                }
+                            });
+                        }
+                    });
+                if precision != self.precision {
+                    self.precision = precision;
+                    self.needs_update = true;
+                }
+                ui.label(format!(
+                    "active precision: {}",
+                    self.active_precision().name()
+                ));
+
+                egui::CollapsingHeader::new("coloring").show(ui, |ui| {
+                    let old_coloring = self.coloring.clone();
+                    self.needs_recolor |= self.coloring.ui(ui, &self.name());
+                    self.needs_update |= self.coloring.needs_iteration(&old_coloring);
+                    if self.coloring.cycle_mode == CycleMode::Histogram {
+                        self.histogram.ui(
+                            ui,
+                            self.coloring.histogram_clip_low,
+                            self.coloring.histogram_clip_high,
+                        );
+                    }
+                });
+                egui::CollapsingHeader::new("orbit trap").show(ui, |ui| {
+                    self.needs_update |= self.trap.ui(ui);
+                });
+                egui::CollapsingHeader::new("performance").show(ui, |ui| {
+                    self.performance_ui(ui);
+                });
+
                 // TODO: checkbox for whether we should match z0 to the point
                 match &mut self.ty {
-                    FractalType::Mandelbrot { z0 } => {
+                    FractalType::Mandelbrot { z0, .. }
+                    | FractalType::BurningShip { z0 }
+                    | FractalType::Tricorn { z0 }
+                    | FractalType::Celtic { z0 }
+                    | FractalType::Buffalo { z0 }
+                    | FractalType::Perpendicular { z0 } => {
                         let mut new_z0 = *z0;
                         ui.add(
                             egui::Slider::new(&mut new_z0.real, -2.0..=2.0)
@@ -558,6 +1625,12 @@ impl Fractal {
                                 .text("z0 imag")
                                 .clamping(egui::SliderClamping::Never),
                         );
+                        if let Some(real) = text_entry(ui, "z0 real", new_z0.real, parse_f64) {
+                            new_z0.real = real;
+                        }
+                        if let Some(imag) = text_entry(ui, "z0 imag", new_z0.imag, parse_f64) {
+                            new_z0.imag = imag;
+                        }
                         if new_z0 != *z0 {
                             *z0 = new_z0;
                             self.needs_update = true;
@@ -575,7 +1648,12 @@ impl Fractal {
                             self.needs_update = true;
                         }
                     }
-                    FractalType::JuliaSet { c } => {
+                    FractalType::JuliaSet { c, .. }
+                    | FractalType::BurningShipJulia { c }
+                    | FractalType::TricornJulia { c }
+                    | FractalType::CelticJulia { c }
+                    | FractalType::BuffaloJulia { c }
+                    | FractalType::PerpendicularJulia { c } => {
                         let mut new_c = *c;
                         ui.add(
                             egui::Slider::new(&mut new_c.real, -2.0..=2.0)
@@ -587,6 +1665,12 @@ impl Fractal {
                                 .text("c imag")
                                 .clamping(egui::SliderClamping::Never),
                         );
+                        if let Some(real) = text_entry(ui, "c real", new_c.real, parse_f64) {
+                            new_c.real = real;
+                        }
+                        if let Some(imag) = text_entry(ui, "c imag", new_c.imag, parse_f64) {
+                            new_c.imag = imag;
+                        }
                         if new_c != *c {
                             *c = new_c;
                             self.needs_update = true;
@@ -601,95 +1685,745 @@ impl Fractal {
         }
     }
 
-    /// render the fractal to a wgpu texture and resets needs_update
-    fn render_to_texture(&mut self) {
-        if !self.needs_update {
+    /// picking the iteration backend, and benchmarking them
+    fn performance_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::from_label("interactive quality")
+            .selected_text(interactive_quality_name(self.interactive_scale))
+            .show_ui(ui, |ui| {
+                for scale in [1, 2, 4, 8] {
+                    ui.selectable_value(
+                        &mut self.interactive_scale,
+                        scale,
+                        interactive_quality_name(scale),
+                    );
+                }
+            })
+            .response
+            .on_hover_text("the resolution while moving, which refines to full once still");
+
+        self.needs_recolor |= self.antialiasing.ui(ui);
+
+        ui.checkbox(&mut self.time_slicing, "time slicing")
+            .on_hover_text(
+                "spread the iteration pass over several frames so deep renders don't freeze the ui",
+            );
+
+        let limits = self.renderer.device.limits();
+        let old_backend = self.backend;
+        let mut compute = matches!(self.backend, Backend::Compute { .. });
+        let mut workgroup_size = match self.backend {
+            Backend::Compute { workgroup_size } => workgroup_size,
+            Backend::Fragment => [8, 8],
+        };
+        ui.checkbox(&mut compute, "compute shader")
+            .on_hover_text("iterate with a compute dispatch instead of a full screen draw");
+        if compute {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::DragValue::new(&mut workgroup_size[0])
+                        .range(1..=limits.max_compute_workgroup_size_x),
+                );
+                ui.label("x");
+                ui.add(
+                    egui::DragValue::new(&mut workgroup_size[1])
+                        .range(1..=limits.max_compute_workgroup_size_y),
+                );
+                ui.label("workgroup size");
+            });
+            // the whole workgroup has a limit too
+            workgroup_size[0] = workgroup_size[0].min(limits.max_compute_invocations_per_workgroup);
+            workgroup_size[1] = workgroup_size[1]
+                .min(limits.max_compute_invocations_per_workgroup / workgroup_size[0]);
+            self.backend = Backend::Compute { workgroup_size };
+        } else {
+            self.backend = Backend::Fragment;
+        }
+        self.needs_update |= self.backend != old_backend;
+
+        let running = self
+            .benchmark
+            .as_ref()
+            .is_some_and(|benchmark| benchmark.current().is_some());
+        if ui
+            .add_enabled(!running, egui::Button::new("benchmark"))
+            .on_hover_text(
+                "time the iteration pass with the fragment shader and a range of workgroup sizes",
+            )
+            .clicked()
+        {
+            self.benchmark = Some(Benchmark::new(
+                self.renderer.adapter_name.clone(),
+                Backend::candidates(&limits),
+            ));
+        }
+        if let Some(benchmark) = &self.benchmark
+            && let Some(backend) = benchmark.ui(ui, self.timer.is_gpu_timed())
+        {
+            self.backend = backend;
+            self.needs_update = true;
+        }
+    }
+
+    /// needs to be called whenever one of the buffers is recreated
+    fn update_render_bind_group(&mut self) {
+        self.render_bind_group = Self::create_render_bind_group(
+            &self.renderer.device,
+            &self.renderer.bind_group_layout,
+            &self.shader_params_buffer,
+            &self.reference_orbit_buffer,
+            &self.orbit_buffer,
+            &self.previous_orbit_buffer,
+            &self.accumulation_buffer,
+            &self.resume_buffer,
+            &self.palette_texture,
+            &self.histogram,
+            &self.stats,
+            &self.image_texture,
+        );
+    }
+
+    /// under perturbation, gets a reference orbit that fits the view.
+    /// a new one is computed on another thread and uploaded once it's in,
+    /// and until then this is false and the last image stays up.
+    fn poll_reference(&mut self) -> bool {
+        if self.active_precision() != Precision::Perturbation {
+            self.reference_job = None;
+            return true;
+        }
+        if let Some((reference, orbit)) = self.reference_job.as_mut().and_then(ReferenceJob::poll) {
+            let orbit_bytes: &[u8] = bytemuck::cast_slice(&orbit);
+            if self.reference_orbit_buffer.size() < orbit_bytes.len() as u64 {
+                self.reference_orbit_buffer =
+                    Self::create_reference_orbit_buffer(&self.renderer.device, orbit.len());
+                self.update_render_bind_group();
+            }
+            self.renderer
+                .queue
+                .write_buffer(&self.reference_orbit_buffer, 0, orbit_bytes);
+            self.reference = Some(reference);
+        }
+        let fits = |reference: &Reference| {
+            reference.is_valid(&self.camera, self.ty, self.max_depth, self.escape_radius)
+        };
+        if self.reference.as_ref().is_some_and(fits) {
+            self.reference_job = None;
+            return true;
+        }
+        // replacing one that's for an old view stops it
+        if !self
+            .reference_job
+            .as_ref()
+            .is_some_and(|job| job.thread.is_some() && fits(&job.reference))
+        {
+            self.reference_job = Some(ReferenceJob::spawn(Reference::new(
+                &self.camera,
+                self.ty,
+                self.max_depth,
+                self.escape_radius,
+            )));
+        }
+        false
+    }
+
+    /// doubles max_depth for keep iterating and auto depth
+    fn raise_max_depth(&mut self) {
+        if self.max_depth < MAX_DEPTH {
+            self.deepen.get_or_insert(self.max_depth);
+            self.max_depth = self.max_depth.saturating_mul(2).min(MAX_DEPTH);
+        }
+    }
+
+    /// for auto depth, doubles max_depth when a lot of pixels escaped late,
+    /// and halves it when hardly any escaped past a quarter of it
+    fn adjust_max_depth(&mut self, counts: StatCounts, pixels: u32) {
+        let pixels = pixels.max(1) as f32;
+        if counts.late as f32 / pixels > AUTO_DEPTH_RAISE_FRACTION {
+            self.raise_max_depth();
+        } else if (counts.past_quarter as f32 / pixels) < AUTO_DEPTH_LOWER_FRACTION
+            && self.max_depth > MIN_AUTO_DEPTH
+        {
+            self.max_depth = (self.max_depth / 2).max(MIN_AUTO_DEPTH);
+            self.needs_update = true;
+        }
+    }
+
+    /// the depth modes go by the stats, and perturbation looks for glitches in them
+    fn wants_stats(&self) -> bool {
+        self.depth_mode != DepthMode::Manual || self.active_precision() == Precision::Perturbation
+    }
+
+    /// moves the reference onto the pixel at index in the last pass, which glitched,
+    /// and starts the pass over with it. that pixel outlived the reference,
+    /// so it goes deeper and the pixels that ran off the end of the last one don't have to.
+    /// gives up after MAX_GLITCH_MOVES so a view that keeps glitching still settles.
+    fn move_reference_to_glitch(&mut self, index: u32) {
+        let Some(reference) = &self.reference else {
+            return;
+        };
+        let width = self.iterate_texture.width();
+        let height = self.iterate_texture.height();
+        let (x, y) = (index % width, index / width);
+        // the same as fragment_position in the shader
+        let position = [
+            (x as f64 + 0.5) / width as f64 * 2.0 - 1.0,
+            1.0 - (y as f64 + 0.5) / height as f64 * 2.0,
+        ];
+        let offset = [
+            position[0] * self.camera.radius_real,
+            position[1] * self.camera.radius_real * height as f64 / width as f64,
+        ];
+        // is_valid only keeps references within radius_real of the center
+        if reference.glitch_moves >= MAX_GLITCH_MOVES
+            || y >= height
+            || offset[1].abs() >= self.camera.radius_real
+            || self.reference_job.is_some()
+        {
             return;
         }
-        self.needs_update = false;
+        self.reference_job = Some(ReferenceJob::spawn(reference.moved(&self.camera, offset)));
+        self.reference = None;
+        self.needs_update = true;
+    }
+
+    /// how much of the iteration pass in progress is done
+    /// or of the reference orbit while it's computing
+    pub(crate) fn progress(&self) -> Option<f32> {
+        if let Some(job) = &self.reference_job {
+            return Some(job.progress());
+        }
+        self.pass
+            .as_ref()
+            .map(|pass| pass.next_row as f32 / self.iterate_texture.height() as f32)
+    }
+
+    /// whether render_to_texture has anything to do, including picking up readbacks,
+    /// so the app only keeps repainting until it's done
+    pub(crate) fn has_work(&self) -> bool {
+        self.needs_update
+            || self.needs_recolor
+            || self.deepen.is_some()
+            || self.pan_shift != [0, 0]
+            || self.pass.is_some()
+            || self.samples < self.antialiasing.sample_count()
+            || self
+                .benchmark
+                .as_ref()
+                .is_some_and(|benchmark| benchmark.current().is_some())
+            || (self.coloring.cycle_mode == CycleMode::Histogram
+                && self.histogram.readback.is_pending())
+            || (self.wants_stats() && self.stats.is_pending())
+            || self.timer.is_pending()
+            || self.reference_job.is_some()
+    }
+
+    pub(crate) fn id(&self) -> usize {
+        self.id
+    }
+
+    /// max_depth and the fraction of pixels that hit it, when auto depth is picking it
+    pub(crate) fn auto_depth(&self) -> Option<(u32, f32)> {
+        (self.depth_mode == DepthMode::Auto).then_some((self.max_depth, self.unresolved))
+    }
 
+    /// picks up whatever the gpu has sent back: the last timed slice's time,
+    /// and the last pass's histogram and stats
+    fn poll_readbacks(&mut self) {
+        // a benchmark frame's time goes to the benchmark, and any other's to the rate,
+        // at most doubled so one quick slice doesn't make the next one huge.
+        // a time that failed to read back just doesn't count.
+        if let Some(seconds) = self.timer.poll(&self.renderer.device, &self.renderer.queue) {
+            let benchmarked = std::mem::take(&mut self.timing_benchmark);
+            match (seconds, &mut self.benchmark) {
+                (Ok(seconds), Some(benchmark)) if benchmarked => benchmark.record(seconds),
+                (Ok(seconds), _) if !benchmarked => {
+                    self.iteration_rate = (self.timed_cost * 1e-3 / seconds.max(1e-6))
+                        .min(2.0 * self.iteration_rate)
+                        .max(1.0);
+                }
+                _ => {}
+            }
+        }
+        // the percentiles of the last iteration pass came back, so recolor with them
+        if self.histogram.poll(&self.renderer.device)
+            && self.coloring.cycle_mode == CycleMode::Histogram
+        {
+            self.needs_recolor = true;
+        }
+        // only the newest pass's stats say anything about the current max_depth
+        if let Some((counts, pixels)) = self.stats.poll(&self.renderer.device)
+            && !self.stats.is_pending()
+        {
+            self.unresolved = counts.unresolved as f32 / pixels.max(1) as f32;
+            match self.depth_mode {
+                DepthMode::Manual => {}
+                // keep going deeper while the last pass still changed something,
+                // once the view is still
+                DepthMode::KeepIterating => {
+                    if counts.changed > 0
+                        && !self.needs_update
+                        && self.pan_shift == [0, 0]
+                        && self.render_scale == self.min_render_scale()
+                    {
+                        self.raise_max_depth();
+                    }
+                }
+                DepthMode::Auto => self.adjust_max_depth(counts, pixels),
+            }
+            if counts.glitched > 0 {
+                self.move_reference_to_glitch(counts.glitch_pixel - 1);
+            }
+        }
+    }
+
+    /// once everything's drawn, copies the last pass's counts to be read back
+    /// if they didn't fit in behind the readback before them
+    fn read_back_leftover_counts(&mut self) {
+        let histogram = self.coloring.cycle_mode == CycleMode::Histogram
+            && self.histogram.readback.is_pending();
+        let stats = self.wants_stats() && self.stats.is_pending();
+        if !histogram && !stats {
+            return;
+        }
         let mut command_encoder =
-            self.device
+            self.renderer
+                .device
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("command_encoder"),
+                    label: Some("readback_command_encoder"),
                 });
+        if histogram {
+            self.histogram.readback.encode(&mut command_encoder);
+        }
+        if stats {
+            self.stats.encode_readback(&mut command_encoder);
+        }
+        self.renderer.queue.submit([command_encoder.finish()]);
+        self.histogram.readback.start();
+        self.stats.start_readback();
+    }
+
+    /// decides how the next iteration pass goes, after a restart, for the next antialiasing sample,
+    /// or to pick up from an abandoned one. only the pixels that changed get iterated:
+    /// after just a pan the ones that weren't on screen,
+    /// and after just raising max_depth the ones that hadn't escaped.
+    /// None if the orbits are already right and it only needs recoloring.
+    fn new_pass(
+        &mut self,
+        abandoned: Option<IterationPass>,
+        restart: bool,
+        deepen: Option<u32>,
+        benchmark_backend: Option<Backend>,
+        new_size: wgpu::Extent3d,
+    ) -> Option<IterationPass> {
+        let precision = self.active_precision();
+        if restart {
+            self.samples = 0;
+            self.needs_recolor = false;
+        }
+        let jitter = self.antialiasing.jitter(self.samples);
+        self.samples += 1;
 
-        self.queue.write_buffer(
+        let pan = std::mem::take(&mut self.pan_shift);
+        // where the last finished orbits are, relative to the view.
+        // an abandoned pan still has them in previous_orbit_buffer,
+        // but anything else abandoned leaves nothing whole to go from.
+        let previous = match &abandoned {
+            None => Some(pan),
+            Some(abandoned) => abandoned
+                .shift
+                .map(|shift| [shift[0] + pan[0], shift[1] + pan[1]]),
+        };
+        self.needs_update |= previous.is_none();
+        let shift = previous.unwrap_or([0, 0]);
+        let same_size = self.iterate_texture.size() == new_size;
+        // the resumes are only good for the orbits exactly as they are
+        let resume = deepen.is_some()
+            && !self.needs_update
+            && self.resumable
+            && self.ty.supports_resume(precision)
+            && benchmark_backend.is_none()
+            && same_size
+            && shift == [0, 0]
+            && jitter == self.orbits_jitter;
+        self.needs_update |= deepen.is_some() && !resume;
+        // the benchmark times full iteration passes
+        let reuse = !self.needs_update
+            && !resume
+            && benchmark_backend.is_none()
+            && same_size
+            && shift[0].unsigned_abs() < new_size.width
+            && shift[1].unsigned_abs() < new_size.height
+            && jitter == self.orbits_jitter;
+        // the benchmark times the iteration pass, so it needs one every frame,
+        // and each sample needs orbits at its own offset
+        let iterate = self.needs_update
+            || resume
+            || shift != [0, 0]
+            || abandoned.is_some()
+            || benchmark_backend.is_some()
+            || jitter != self.orbits_jitter;
+        if !iterate {
+            return None;
+        }
+        self.orbits_jitter = jitter;
+        // until it's done
+        self.resumable = false;
+        // poll_reference already has one that fits for perturbation
+        if precision != Precision::Perturbation {
+            self.reference = None;
+        }
+        // keep iterating goes by what the extra depth found,
+        // or by what escaped late after anything else.
+        // the later antialiasing samples don't count so they don't start over again.
+        let changed_depth = match deepen {
+            Some(old_max_depth) => old_max_depth,
+            None if self.samples == 1 => self.max_depth / 2,
+            None => u32::MAX,
+        };
+        Some(IterationPass {
+            next_row: 0,
+            shift: reuse.then_some(shift),
+            copy_previous: reuse && abandoned.is_none(),
+            jitter,
+            resume,
+            changed_depth,
+        })
+    }
+
+    /// uploads the params for this slice, or for just recoloring without one,
+    /// and whatever the coloring needs
+    fn write_params(
+        &mut self,
+        new_size: wgpu::Extent3d,
+        pass: Option<&IterationPass>,
+        slice: std::ops::Range<u32>,
+    ) {
+        self.renderer.queue.write_buffer(
             &self.shader_params_buffer,
             0,
             bytemuck::bytes_of(&Params::new(
-                self.camera,
-                self.size.x as u32,
-                self.size.y as u32,
+                &self.camera,
+                new_size.width,
+                new_size.height,
                 self.ty,
                 self.max_depth,
                 self.escape_radius,
+                self.active_precision(),
+                self.reference.as_ref(),
+                &self.coloring,
+                &self.trap,
+                pass.and_then(|pass| pass.shift),
+                pass.map_or(self.orbits_jitter, |pass| pass.jitter),
+                self.samples,
+                pass.is_some_and(|pass| pass.resume),
+                pass.map_or(u32::MAX, |pass| pass.changed_depth),
+                slice,
+            )),
+        );
+        self.renderer.queue.write_texture(
+            self.palette_texture.as_image_copy(),
+            bytemuck::cast_slice(&self.coloring.palette.texels()),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: None,
+                rows_per_image: None,
+            },
+            self.palette_texture.size(),
+        );
+        if !Arc::ptr_eq(&self.image_texture_source, &self.coloring.texture) {
+            self.image_texture_source = self.coloring.texture.clone();
+            self.image_texture = Self::create_image_texture(
+                &self.renderer.device,
+                &self.renderer.queue,
+                &self.image_texture_source,
+            );
+            self.update_render_bind_group();
+        }
+        self.renderer.queue.write_buffer(
+            &self.histogram.cdf_buffer,
+            0,
+            bytemuck::cast_slice(&self.histogram.cdf(
+                self.coloring.histogram_clip_low,
+                self.coloring.histogram_clip_high,
             )),
         );
+    }
 
-        // render pass
-        command_encoder.push_debug_group("render_pass");
-        {
-            let new_size = wgpu::Extent3d {
-                width: self.size.x as u32,
-                height: self.size.y as u32,
-                depth_or_array_layers: 1,
-            };
-            if self.texture.size() != new_size {
-                // println!("self.texture.size() != new_size");
-                self.texture = self.device.create_texture(&wgpu::TextureDescriptor {
+    /// iterates the rows in slice of pass with backend,
+    /// resizing the per-pixel buffers first if the view changed size
+    fn encode_iteration_slice(
+        &mut self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        pass: &IterationPass,
+        slice: std::ops::Range<u32>,
+        new_size: wgpu::Extent3d,
+        backend: Backend,
+        timed: bool,
+    ) {
+        if self.iterate_texture.size() != new_size {
+            self.iterate_texture = Self::create_iterate_texture(&self.renderer.device, new_size);
+            let render_size = egui::Vec2::new(new_size.width as f32, new_size.height as f32);
+            self.orbit_buffer =
+                Self::create_orbit_buffer(&self.renderer.device, "orbit_buffer", render_size);
+            self.previous_orbit_buffer = Self::create_orbit_buffer(
+                &self.renderer.device,
+                "previous_orbit_buffer",
+                render_size,
+            );
+            self.accumulation_buffer =
+                Self::create_accumulation_buffer(&self.renderer.device, render_size);
+            self.update_render_bind_group();
+        }
+        // only as big as the view when there's anything to resume
+        let resume_pixels = if self.ty.supports_resume(self.active_precision()) {
+            new_size.width as u64 * new_size.height as u64
+        } else {
+            1
+        };
+        if self.resume_buffer.size() != resume_pixels * RESUME_SIZE {
+            self.resume_buffer = Self::create_resume_buffer(&self.renderer.device, resume_pixels);
+            self.update_render_bind_group();
+        }
+        if pass.next_row == 0 {
+            if pass.copy_previous {
+                command_encoder.copy_buffer_to_buffer(
+                    &self.orbit_buffer,
+                    0,
+                    &self.previous_orbit_buffer,
+                    0,
+                    self.orbit_buffer.size(),
+                );
+            }
+            // only the first sample gets counted
+            if self.samples == 1 {
+                self.histogram.readback.clear(command_encoder);
+            }
+            self.stats
+                .clear(command_encoder, new_size.width * new_size.height);
+        }
+        match backend {
+            Backend::Fragment => {
+                let iterate_view = self
+                    .iterate_texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+                let mut render_pass =
+                    command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("iterate_pass"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: &iterate_view,
+                            resolve_target: None,
+                            ops: wgpu::Operations::default(),
+                        })],
+                        depth_stencil_attachment: None,
+                        timestamp_writes: self.timer.render_timestamp_writes().filter(|_| timed),
+                        occlusion_query_set: None,
+                    });
+                render_pass.set_pipeline(&self.renderer.iterate_pipeline);
+                render_pass.set_bind_group(0, &self.render_bind_group, &[]);
+                render_pass.set_scissor_rect(0, slice.start, new_size.width, slice.len() as u32);
+                render_pass.draw(0..6, 0..1);
+            }
+            Backend::Compute {
+                workgroup_size: [x, y],
+            } => {
+                let compute_pipeline = self.renderer.compute_pipeline([x, y]);
+                let mut compute_pass =
+                    command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                        label: Some("compute_pass"),
+                        timestamp_writes: self.timer.compute_timestamp_writes().filter(|_| timed),
+                    });
+                compute_pass.set_pipeline(&compute_pipeline);
+                compute_pass.set_bind_group(0, &self.render_bind_group, &[]);
+                compute_pass.dispatch_workgroups(
+                    new_size.width.div_ceil(x),
+                    (slice.len() as u32).div_ceil(y),
+                    1,
+                );
+            }
+        }
+        if timed {
+            self.timer.resolve(command_encoder);
+        }
+    }
+
+    /// colors the finished orbits into texture, which egui shows
+    fn encode_color_pass(
+        &mut self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        new_size: wgpu::Extent3d,
+    ) {
+        if self.texture.size() != new_size {
+            // println!("self.texture.size() != new_size");
+            self.texture = self
+                .renderer
+                .device
+                .create_texture(&wgpu::TextureDescriptor {
                     label: Some("texture"),
                     size: new_size,
                     mip_level_count: 1,
                     sample_count: 1,
                     dimension: wgpu::TextureDimension::D2,
-                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                    format: TEXTURE_FORMAT,
                     usage: wgpu::TextureUsages::TEXTURE_BINDING
                         | wgpu::TextureUsages::RENDER_ATTACHMENT
                         | wgpu::TextureUsages::COPY_DST,
-                    view_formats: &[wgpu::TextureFormat::Rgba8UnormSrgb],
+                    view_formats: &[TEXTURE_FORMAT],
                 });
-                self.renderer.write().update_egui_texture_from_wgpu_texture(
-                    &self.device,
+            self.renderer
+                .egui_renderer
+                .write()
+                .update_egui_texture_from_wgpu_texture(
+                    &self.renderer.device,
                     &self
                         .texture
                         .create_view(&wgpu::TextureViewDescriptor::default()),
                     eframe::wgpu::FilterMode::Nearest,
                     self.texture_id,
                 );
-            }
-            let texture_view = self
-                .texture
-                .create_view(&wgpu::TextureViewDescriptor::default());
-            let color_attachments = [Some(wgpu::RenderPassColorAttachment {
+        }
+        let texture_view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        // self.renderer.queue.write_buffer(
+        //     &self.vertex_buffer,
+        //     0,
+        //     bytemuck::bytes_of(&get_triangle(
+        //         view_settings.particle_radius * view_settings.zoom_scale,
+        //     )),
+        // );
+        let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("render_pass_descriptor"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                 view: &texture_view,
                 resolve_target: None,
                 ops: wgpu::Operations::default(),
-            })];
-            // self.queue.write_buffer(
-            //     &self.vertex_buffer,
-            //     0,
-            //     bytemuck::bytes_of(&get_triangle(
-            //         view_settings.particle_radius * view_settings.zoom_scale,
-            //     )),
-            // );
-            let render_pass_descriptor = wgpu::RenderPassDescriptor {
-                label: Some("render_pass_descriptor"),
-                color_attachments: &color_attachments,
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.renderer.color_pipeline);
+        render_pass.set_bind_group(0, &self.render_bind_group, &[]);
+        // render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        // render_pass.draw(0..3, 0..sim_settings.particle_n as _);
+        render_pass.draw(0..6, 0..1);
+    }
+
+    /// render the fractal to a wgpu texture and resets needs_update, needs_recolor and pan_shift.
+    /// only reruns the iteration pass if needs_update is set, it was panned, max_depth went up,
+    /// or for the next antialiasing sample, see new_pass.
+    /// with time slicing the iteration pass does as many rows as fit in budget milliseconds,
+    /// and the texture keeps the last finished image until the last row is done.
+    /// returns how many milliseconds of gpu time it's guessed to take.
+    pub(crate) fn render_to_texture(&mut self, budget: f64) -> f64 {
+        self.poll_readbacks();
+        let benchmark_backend = self.benchmark.as_ref().and_then(Benchmark::current);
+        // every benchmark frame is timed, so the next one waits for the last one's time
+        if benchmark_backend.is_some() && self.timer.is_pending() {
+            return 0.0;
+        }
+        if !self.poll_reference() {
+            return 0.0;
+        }
+        let deepen = std::mem::take(&mut self.deepen);
+        // anything changing starts the antialiasing over,
+        // otherwise each frame adds a sample until there's enough.
+        // recoloring waits for the pass in progress, since it colors whatever's in orbit_buffer.
+        let restart = self.needs_update
+            || deepen.is_some()
+            || self.pan_shift != [0, 0]
+            || benchmark_backend.is_some()
+            || (self.needs_recolor && self.pass.is_none());
+        if !restart && self.pass.is_none() && self.samples >= self.antialiasing.sample_count() {
+            self.read_back_leftover_counts();
+            return 0.0;
+        }
+
+        let new_size = wgpu::Extent3d {
+            width: (self.size.x as u32).div_ceil(self.render_scale),
+            height: (self.size.y as u32).div_ceil(self.render_scale),
+            depth_or_array_layers: 1,
+        };
+        let pass = match self.pass.take() {
+            Some(pass) if !restart => Some(pass),
+            abandoned => self.new_pass(abandoned, restart, deepen, benchmark_backend, new_size),
+        };
+        self.needs_update = false;
+        // the benchmark times whole passes
+        let slicing = self.time_slicing && benchmark_backend.is_none();
+        let row_cost = new_size.width as f64 * self.max_depth as f64;
+        let slice = pass.as_ref().map_or(0..0, |pass| {
+            let remaining = new_size.height - pass.next_row;
+            let rows = if slicing {
+                ((budget * self.iteration_rate / row_cost) as u32).clamp(1, remaining)
+            } else {
+                remaining
             };
-            let mut render_pass = command_encoder.begin_render_pass(&render_pass_descriptor);
-            render_pass.set_pipeline(&self.render_pipeline);
-            render_pass.set_bind_group(0, &self.render_bind_group, &[]);
-            // render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            // render_pass.draw(0..3, 0..sim_settings.particle_n as _);
-            render_pass.draw(0..6, 0..1);
+            pass.next_row..pass.next_row + rows
+        });
+        let done = slice.end == new_size.height || pass.is_none();
+        self.write_params(new_size, pass.as_ref(), slice.clone());
+
+        let mut command_encoder =
+            self.renderer
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("command_encoder"),
+                });
+        command_encoder.push_debug_group("render_pass");
+        // the rate counts every pixel as going all the way to max_depth, which the ones copied
+        // after a pan or left alone by a resume don't, so timing those would inflate it.
+        // only one time can be read back at once.
+        let timed = benchmark_backend.is_some()
+            || (slicing
+                && !self.timer.is_pending()
+                && pass
+                    .as_ref()
+                    .is_some_and(|pass| pass.shift.is_none() && !pass.resume));
+        if let Some(pass) = &pass {
+            self.encode_iteration_slice(
+                &mut command_encoder,
+                pass,
+                slice.clone(),
+                new_size,
+                benchmark_backend.unwrap_or(self.backend),
+                timed,
+            );
+        }
+        if done {
+            self.encode_color_pass(&mut command_encoder, new_size);
         }
         command_encoder.pop_debug_group();
+        // the counts are only whole once the pass is
+        if done && self.coloring.cycle_mode == CycleMode::Histogram {
+            self.histogram.readback.encode(&mut command_encoder);
+        }
+        if done && self.wants_stats() {
+            self.stats.encode_readback(&mut command_encoder);
+        }
+        if let Some(pass) = &pass
+            && done
+        {
+            // a pan leaves the reused pixels' resumes where they were
+            self.resumable =
+                self.ty.supports_resume(self.active_precision()) && pass.shift.is_none();
+        }
+        self.pass = pass.filter(|_| !done).map(|pass| IterationPass {
+            next_row: slice.end,
+            ..pass
+        });
 
-        self.queue.submit([command_encoder.finish()]);
+        let submitted = std::time::Instant::now();
+        self.renderer.queue.submit([command_encoder.finish()]);
+        self.histogram.readback.start();
+        self.stats.start_readback();
+        let cost = slice.len() as f64 * row_cost;
+        // read back without blocking, so the ui doesn't wait on the slice
+        if timed {
+            self.timer.start_readback(&self.renderer.queue, submitted);
+            self.timed_cost = cost;
+            self.timing_benchmark = benchmark_backend.is_some();
+        }
         // dbg!(cpu_readable_buffer);
+        cost / self.iteration_rate
     }
 }
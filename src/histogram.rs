@@ -0,0 +1,148 @@
+use eframe::{egui, wgpu};
+
+use crate::Readback;
+
+/// how many bins the iteration pass sorts the escaped depths into, mirrored in shader.wgsl
+pub(crate) const HISTOGRAM_BINS: usize = 1024;
+
+/// the distribution of the escaped depths of the last iteration pass,
+/// for mapping depths to colors by percentile.
+/// the gpu counts them and we read the counts back a frame or so later.
+pub(crate) struct Histogram {
+    /// HISTOGRAM_BINS atomic counts, filled by the iteration pass
+    pub(crate) readback: Readback,
+    /// HISTOGRAM_BINS + 1 points of the clipped cumulative histogram, for the coloring pass
+    pub(crate) cdf_buffer: wgpu::Buffer,
+    counts: Vec<u32>,
+}
+impl Histogram {
+    pub(crate) fn new(device: &wgpu::Device) -> Self {
+        Self {
+            readback: Readback::new(
+                device,
+                "histogram",
+                (HISTOGRAM_BINS * std::mem::size_of::<u32>()) as u64,
+            ),
+            cdf_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("cdf_buffer"),
+                size: ((HISTOGRAM_BINS + 1) * std::mem::size_of::<f32>()) as u64,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            counts: vec![0; HISTOGRAM_BINS],
+        }
+    }
+
+    /// picks up the counts if the readback finished.
+    /// returns whether they changed, which they don't when the same view gets iterated again,
+    /// or when the readback failed and the old counts stay.
+    pub(crate) fn poll(&mut self, device: &wgpu::Device) -> bool {
+        let counts = &mut self.counts;
+        self.readback
+            .poll(device, |data| {
+                let new_counts: &[u32] = bytemuck::cast_slice(data);
+                let changed = counts.as_slice() != new_counts;
+                counts.copy_from_slice(new_counts);
+                changed
+            })
+            .and_then(Result::ok)
+            .unwrap_or(false)
+    }
+
+    /// the fraction of escaped pixels in the bins before each bin edge,
+    /// with the bottom clip_low and top clip_high stretched out to 0 and 1
+    pub(crate) fn cdf(&self, clip_low: f32, clip_high: f32) -> Vec<f32> {
+        clipped_cdf(&self.counts, clip_low, clip_high)
+    }
+
+    /// the counts on a log scale with the clipped cdf over them
+    pub(crate) fn ui(&self, ui: &mut egui::Ui, clip_low: f32, clip_high: f32) {
+        let (rect, _) = ui.allocate_exact_size(
+            egui::Vec2::new(ui.available_width(), 64.0),
+            egui::Sense::hover(),
+        );
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+        // one bar per column of pixels, with the bins that fall in it summed
+        let columns = (rect.width() as usize).clamp(1, HISTOGRAM_BINS);
+        let column_counts: Vec<u64> = (0..columns)
+            .map(|column| {
+                let bins =
+                    column * HISTOGRAM_BINS / columns..(column + 1) * HISTOGRAM_BINS / columns;
+                self.counts[bins].iter().map(|&count| count as u64).sum()
+            })
+            .collect();
+        let max = column_counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let column_width = rect.width() / columns as f32;
+        for (column, &count) in column_counts.iter().enumerate() {
+            let height = (1.0 + count as f32).ln() / (1.0 + max).ln() * rect.height();
+            let left = rect.left() + column as f32 * column_width;
+            painter.rect_filled(
+                egui::Rect::from_min_max(
+                    egui::Pos2::new(left, rect.bottom() - height),
+                    egui::Pos2::new(left + column_width, rect.bottom()),
+                ),
+                0.0,
+                ui.visuals().weak_text_color(),
+            );
+        }
+
+        let cdf = self.cdf(clip_low, clip_high);
+        painter.add(egui::Shape::line(
+            cdf.iter()
+                .enumerate()
+                .map(|(i, fraction)| {
+                    egui::Pos2::new(
+                        rect.left() + i as f32 / HISTOGRAM_BINS as f32 * rect.width(),
+                        rect.bottom() - fraction * rect.height(),
+                    )
+                })
+                .collect(),
+            egui::Stroke::new(1.0, ui.visuals().strong_text_color()),
+        ));
+        ui.label("escaped depths on a log scale, and the percentile they get colored by");
+    }
+}
+
+fn clipped_cdf(counts: &[u32], clip_low: f32, clip_high: f32) -> Vec<f32> {
+    let total = counts.iter().map(|&count| count as u64).sum::<u64>().max(1) as f32;
+    let range = (1.0 - clip_low - clip_high).max(f32::EPSILON);
+    let mut sum = 0;
+    std::iter::once(0.0)
+        .chain(counts.iter().map(|&count| {
+            sum += count as u64;
+            sum as f32 / total
+        }))
+        .map(|fraction| ((fraction - clip_low) / range).clamp(0.0, 1.0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clipped_cdf_is_monotone_and_ends_at_one() {
+        let counts: Vec<u32> = (0..HISTOGRAM_BINS as u32)
+            .map(|i| (i * 7919) % 101 + if i % 3 == 0 { 0 } else { 1000 })
+            .collect();
+        for (clip_low, clip_high) in [(0.0, 0.0), (0.05, 0.0), (0.0, 0.1), (0.2, 0.3), (0.5, 0.5)] {
+            let cdf = clipped_cdf(&counts, clip_low, clip_high);
+            assert_eq!(cdf.len(), HISTOGRAM_BINS + 1);
+            assert_eq!(cdf[0], 0.0);
+            assert_eq!(cdf[HISTOGRAM_BINS], 1.0, "{clip_low} {clip_high}");
+            assert!(
+                cdf.windows(2).all(|pair| pair[0] <= pair[1]),
+                "{clip_low} {clip_high}"
+            );
+        }
+
+        // everything in one bin jumps straight from 0 to 1
+        let mut counts = vec![0; HISTOGRAM_BINS];
+        counts[10] = 5;
+        let cdf = clipped_cdf(&counts, 0.1, 0.1);
+        assert!(cdf[..=10].iter().all(|&fraction| fraction == 0.0));
+        assert!(cdf[11..].iter().all(|&fraction| fraction == 1.0));
+    }
+}
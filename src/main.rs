@@ -1,13 +1,61 @@
+mod antialiasing;
+mod benchmark;
+mod big;
+mod coloring;
 mod fractal;
+mod histogram;
+mod palette;
+mod readback;
+mod renderer;
+mod scheduler;
+mod stats;
+mod texture_image;
 
+use antialiasing::*;
+use benchmark::*;
+use big::*;
+use coloring::*;
 use eframe::egui;
 use fractal::*;
+use histogram::*;
+use palette::*;
+use readback::*;
+use renderer::*;
+use scheduler::*;
+use stats::*;
+use std::sync::Arc;
+use texture_image::*;
 
 fn main() -> eframe::Result {
     // std::env::set_var("RUST_BACKTRACE", "1");
     // env_logger::init();
 
-    let native_options = eframe::NativeOptions::default();
+    let native_options = eframe::NativeOptions {
+        wgpu_options: eframe::egui_wgpu::WgpuConfiguration {
+            wgpu_setup: eframe::egui_wgpu::WgpuSetup::CreateNew(
+                eframe::egui_wgpu::WgpuSetupCreateNew {
+                    // ask for f64 in shaders when the adapter has it, for the double precision,
+                    // timestamp queries for the benchmark,
+                    // and as big a buffer as it can bind for the per pixel buffers
+                    device_descriptor: std::sync::Arc::new(|adapter| {
+                        let default = eframe::egui_wgpu::WgpuSetupCreateNew::default();
+                        let mut descriptor = (default.device_descriptor)(adapter);
+                        descriptor.required_features |= adapter.features()
+                            & (eframe::wgpu::Features::SHADER_F64
+                                | eframe::wgpu::Features::TIMESTAMP_QUERY);
+                        let limits = adapter.limits();
+                        descriptor.required_limits.max_storage_buffer_binding_size =
+                            limits.max_storage_buffer_binding_size;
+                        descriptor.required_limits.max_buffer_size = limits.max_buffer_size;
+                        descriptor
+                    }),
+                    ..Default::default()
+                },
+            ),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
 
     eframe::run_native(
         "fractal",
@@ -16,10 +64,12 @@ fn main() -> eframe::Result {
     )
 }
 
+/// f64 so the z0s and cs typed in keep their digits,
+/// which the shader gets as pairs of f32s for the higher precisions
 #[derive(Clone, Copy, Debug, PartialEq)]
 struct Complex {
-    real: f32,
-    imag: f32,
+    real: f64,
+    imag: f64,
 }
 impl Complex {
     const ZERO: Complex = Complex {
@@ -35,16 +85,42 @@ impl Complex {
     // }
 }
 
-#[derive(Clone, Copy, Debug)]
+/// how far from the origin the center can go,
+/// which leaves the 32 bit integer part of a BigFixed room for any pan
+const MAX_CENTER: f64 = 2147483648.0;
+
+#[derive(Clone, Debug)]
 // struct Camera {
 //     lo: Complex,
 //     hi: Complex,
 // }
 struct Camera {
-    center: Complex,
-    radius_real: f32,
+    /// stored at full precision so we can zoom past f32 and f64
+    center: BigComplex,
+    radius_real: f64,
+    /// the part of the panning that's less than a pixel, which the center doesn't include yet.
+    /// in screen pixels.
+    pan_remainder: egui::Vec2,
 }
 impl Camera {
+    /// how many fractional limbs the center needs to be exact to well below a pixel
+    fn frac_limbs(&self) -> usize {
+        // 64 bits below the radius is plenty for any screen
+        ((-self.radius_real.log2()).max(0.0) as usize + 64).div_ceil(32)
+    }
+
+    /// moves the center by a (possibly tiny) offset without losing precision.
+    /// stops at MAX_CENTER.
+    fn translate(&mut self, real: f64, imag: f64) {
+        let frac_limbs = self.frac_limbs().max(self.center.frac_limbs());
+        self.center = BigComplex {
+            real: (&self.center.real + &BigFixed::from_f64(real, frac_limbs))
+                .clamp_magnitude(MAX_CENTER),
+            imag: (&self.center.imag + &BigFixed::from_f64(imag, frac_limbs))
+                .clamp_magnitude(MAX_CENTER),
+        };
+    }
+
     // fn from_center_radius(center: Complex, radius: f32) -> Self {
     //     Self {
     //         lo: Complex {
@@ -78,11 +154,9 @@ impl Default for Camera {
         //     },
         // }
         Self {
-            center: Complex {
-                real: 0.0,
-                imag: 0.0,
-            },
+            center: BigComplex::zero(2),
             radius_real: 2.0,
+            pan_remainder: egui::Vec2::ZERO,
         }
     }
 }
@@ -121,7 +195,8 @@ impl Default for Camera {
 // }
 
 struct App {
-    render_state: eframe::egui_wgpu::RenderState,
+    renderer: Arc<FractalRenderer>,
+    scheduler: RenderScheduler,
     main: Fractal,
     // windows: Vec<FractalWindow>,
     settings_main: bool,
@@ -137,10 +212,10 @@ struct App {
 }
 impl App {
     fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let render_state = cc.wgpu_render_state.clone().unwrap();
+        let renderer = Arc::new(FractalRenderer::new(cc.wgpu_render_state.as_ref().unwrap()));
         Self {
-            // main: Fractal::default(&render_state, 0, FractalType::new_mandelbrot(Complex::ZERO)),
-            main: Fractal::default(&render_state, 0, FractalType::new_metabrot(8)),
+            // main: Fractal::default(&renderer, 0, FractalType::new_mandelbrot(Complex::ZERO, 2.0)),
+            main: Fractal::default(&renderer, 0, FractalType::new_metabrot(8)),
             settings_main: false,
             fractal_windows: vec![],
             settings_windows: vec![],
@@ -148,7 +223,8 @@ impl App {
             show_point: false,
             show_overlay: true,
             // trackpad: false,
-            render_state,
+            renderer,
+            scheduler: RenderScheduler::new(),
             fractal_counter: 1,
             dts: egui::util::History::new(2..100, 1.0),
         }
@@ -156,7 +232,6 @@ impl App {
 }
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        ctx.request_repaint();
         egui::CentralPanel::default()
             .frame(egui::Frame::new())
             .show(ctx, |ui| {
@@ -169,6 +244,15 @@ impl eframe::App for App {
                     self.show_overlay = !self.show_overlay;
                 }
 
+                self.main.follow_point(self.point);
+                for fractal in &mut self.fractal_windows {
+                    fractal.follow_point(self.point);
+                }
+                // the ids of the windows that are open and not collapsed, and the one hovered,
+                // for the scheduler
+                let mut shown = vec![];
+                let mut hovered = None;
+
                 // TODO: possibly fractals should know whether they're main
                 // TODO: possibly fractals should know whether their settings are open
                 // TODO: possibly fractals should know whether they should show the point
@@ -176,6 +260,7 @@ impl eframe::App for App {
                 {
                     let FractalUiResponse {
                         should_open_settings,
+                        hovered: main_hovered,
                         // new_point,
                     } = self.main.ui(
                         ctx,
@@ -187,6 +272,9 @@ impl eframe::App for App {
                         },
                     );
                     self.settings_main |= should_open_settings;
+                    if main_hovered {
+                        hovered = Some(self.main.id());
+                    }
                     // if let Some(point) = new_point {
                     //     self.point = point;
                     // }
@@ -217,6 +305,7 @@ impl eframe::App for App {
                             .show(ctx, |ui| {
                                 let FractalUiResponse {
                                     should_open_settings,
+                                    hovered: window_hovered,
                                     // new_point,
                                 } = fractal.ui(
                                     ctx,
@@ -228,6 +317,10 @@ impl eframe::App for App {
                                     },
                                 );
                                 self.settings_windows[i] |= should_open_settings;
+                                shown.push(fractal.id());
+                                if window_hovered {
+                                    hovered = Some(fractal.id());
+                                }
                                 // if let Some(point) = new_point {
                                 //     self.point = point;
                                 // }
@@ -274,6 +367,7 @@ impl eframe::App for App {
                                                 average_dt,
                                                 1.0 / average_dt,
                                             ));
+                                            self.scheduler.ui(ui);
                                         }
 
                                         // view stuff
@@ -284,13 +378,59 @@ impl eframe::App for App {
                                             //     self.main.camera().center.imag,
                                             //     self.main.camera().radius_real,
                                             // );
-                                            // TODO: clicking copies the camera?
+                                            let camera = self.main.camera();
+                                            // enough digits to see the center move by a pixel
+                                            let digits =
+                                                (-camera.radius_real.log10()).max(5.0) as usize + 4;
+                                            if ui
+                                                .add(
+                                                    egui::Label::new(format!(
+                                                        "center: {:.*} + {:.*}i\nreal_radius: {:.3e}",
+                                                        digits,
+                                                        camera.center.real,
+                                                        digits,
+                                                        camera.center.imag,
+                                                        camera.radius_real,
+                                                    ))
+                                                    .sense(egui::Sense::click()),
+                                                )
+                                                .on_hover_text("click to copy")
+                                                .clicked()
+                                            {
+                                                // one per line, at full precision,
+                                                // to paste into the settings text boxes
+                                                ctx.copy_text(format!(
+                                                    "{}\n{}\n{}",
+                                                    camera.center.real,
+                                                    camera.center.imag,
+                                                    camera.radius_real,
+                                                ));
+                                            }
                                             ui.label(format!(
-                                            "center: {:12.09} + {:12.09}i\nreal_radius: {:12.09}",
-                                            self.main.camera().center.real,
-                                            self.main.camera().center.imag,
-                                            self.main.camera().radius_real,
-                                        ));
+                                                "precision: {}",
+                                                self.main.active_precision().name()
+                                            ));
+                                            let (samples, sample_count) = self.main.samples();
+                                            if sample_count > 1 {
+                                                ui.label(format!(
+                                                    "samples: {samples}/{sample_count}"
+                                                ));
+                                            }
+                                            if let Some((depth, unresolved)) =
+                                                self.main.auto_depth()
+                                            {
+                                                ui.label(format!(
+                                                    "auto depth: {depth}, unresolved: {:.2}%",
+                                                    unresolved * 100.0
+                                                ));
+                                            }
+                                            if self.main.is_near_precision_limit() {
+                                                ui.colored_label(
+                                                    ui.visuals().warn_fg_color,
+                                                    "pixels are near the precision limit, \
+                                                    try a higher precision in the settings",
+                                                );
+                                            }
                                         }
 
                                         // point stuff
@@ -311,32 +451,25 @@ impl eframe::App for App {
 
                                         // add fractal windows
                                         {
-                                            if ui.button("add mandelbrot").clicked() {
-                                                self.fractal_windows.push(Fractal::default(
-                                                    &self.render_state,
-                                                    self.fractal_counter,
-                                                    FractalType::new_mandelbrot(Complex::ZERO),
-                                                ));
-                                                self.settings_windows.push(false);
-                                                self.fractal_counter += 1;
-                                            }
-                                            if ui.button("add metabrot").clicked() {
-                                                self.fractal_windows.push(Fractal::default(
-                                                    &self.render_state,
-                                                    self.fractal_counter,
-                                                    FractalType::new_metabrot(8),
-                                                ));
-                                                self.settings_windows.push(false);
-                                                self.fractal_counter += 1;
-                                            }
-                                            if ui.button("add julia set").clicked() {
-                                                self.fractal_windows.push(Fractal::default(
-                                                    &self.render_state,
-                                                    self.fractal_counter,
-                                                    FractalType::new_julia(Complex::ZERO),
-                                                ));
-                                                self.settings_windows.push(false);
-                                                self.fractal_counter += 1;
+                                            for pair in FractalType::PAIRS {
+                                                ui.horizontal(|ui| {
+                                                    for ty in pair {
+                                                        if ui
+                                                            .button(format!("add {}", ty.name()))
+                                                            .clicked()
+                                                        {
+                                                            self.fractal_windows.push(
+                                                                Fractal::default(
+                                                                    &self.renderer,
+                                                                    self.fractal_counter,
+                                                                    ty,
+                                                                ),
+                                                            );
+                                                            self.settings_windows.push(false);
+                                                            self.fractal_counter += 1;
+                                                        }
+                                                    }
+                                                });
                                             }
                                         }
                                     });
@@ -344,6 +477,18 @@ impl eframe::App for App {
                         });
                 }
 
+                let busy = self.scheduler.run(
+                    &mut self.main,
+                    self.fractal_windows
+                        .iter_mut()
+                        .filter(|fractal| shown.contains(&fractal.id())),
+                    hovered,
+                );
+                // otherwise it idles until there's input
+                if busy {
+                    ctx.request_repaint();
+                }
+
                 // // TODO: point may not the the correct abstraction
                 // if self.point != prev_point {
                 //     self.main.set_point(self.point);
@@ -0,0 +1,586 @@
+use eframe::egui;
+
+/// how many texels a palette is sampled into for the gpu
+pub(crate) const PALETTE_SIZE: usize = 256;
+/// ultra fractal gradients are indexed 0..400
+const UGR_SIZE: i64 = 400;
+
+/// what space the colors are blended in between stops
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Interpolation {
+    LinearSrgb,
+    Oklab,
+}
+impl Interpolation {
+    const ALL: [Self; 2] = [Self::LinearSrgb, Self::Oklab];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::LinearSrgb => "linear srgb",
+            Self::Oklab => "oklab",
+        }
+    }
+
+    /// what it's called in saved palettes
+    fn key(&self) -> &'static str {
+        match self {
+            Self::LinearSrgb => "linear_srgb",
+            Self::Oklab => "oklab",
+        }
+    }
+
+    fn mix(&self, a: egui::Color32, b: egui::Color32, t: f32) -> egui::Color32 {
+        let a = egui::Rgba::from(a);
+        let b = egui::Rgba::from(b);
+        let [r, g, b] = match self {
+            Self::LinearSrgb => lerp3([a.r(), a.g(), a.b()], [b.r(), b.g(), b.b()], t),
+            Self::Oklab => oklab_to_linear(lerp3(
+                linear_to_oklab([a.r(), a.g(), a.b()]),
+                linear_to_oklab([b.r(), b.g(), b.b()]),
+                t,
+            )),
+        };
+        egui::Color32::from(egui::Rgba::from_rgb(
+            r.clamp(0.0, 1.0),
+            g.clamp(0.0, 1.0),
+            b.clamp(0.0, 1.0),
+        ))
+    }
+}
+
+fn lerp3(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+    ]
+}
+
+// https://bottosson.github.io/posts/oklab/
+// the constants are kept as published
+
+#[allow(clippy::excessive_precision)]
+fn linear_to_oklab([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+#[allow(clippy::excessive_precision)]
+fn oklab_to_linear([l, a, b]: [f32; 3]) -> [f32; 3] {
+    let l_ = (l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m_ = (l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s_ = (l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    [
+        4.0767416621 * l_ - 3.3077115913 * m_ + 0.2309699292 * s_,
+        -1.2684380046 * l_ + 2.6097574011 * m_ - 0.3413193965 * s_,
+        -0.0041960863 * l_ - 0.7034186147 * m_ + 1.7076147010 * s_,
+    ]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ColorStop {
+    /// where the stop is along the palette, in [0, 1)
+    position: f32,
+    color: egui::Color32,
+}
+
+/// a cyclic gradient, so the color after the last stop blends back into the first
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Palette {
+    name: String,
+    /// never empty, but not necessarily sorted since the editor lets them be dragged past each other
+    stops: Vec<ColorStop>,
+    interpolation: Interpolation,
+}
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            stops: [
+                (0.0, egui::Color32::from_rgb(0, 7, 100)),
+                (0.16, egui::Color32::from_rgb(32, 107, 203)),
+                (0.42, egui::Color32::from_rgb(237, 255, 255)),
+                (0.6425, egui::Color32::from_rgb(255, 170, 0)),
+                (0.8575, egui::Color32::from_rgb(0, 2, 0)),
+            ]
+            .into_iter()
+            .map(|(position, color)| ColorStop { position, color })
+            .collect(),
+            interpolation: Interpolation::Oklab,
+        }
+    }
+}
+impl Palette {
+    fn sorted_stops(&self) -> Vec<ColorStop> {
+        let mut stops = self.stops.clone();
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        stops
+    }
+
+    /// the color at t in [0, 1)
+    fn sample(stops: &[ColorStop], interpolation: Interpolation, t: f32) -> egui::Color32 {
+        // the stop at or before t and the one after it, wrapping around the ends
+        let next = stops.partition_point(|stop| stop.position <= t);
+        let prev = stops[(next + stops.len() - 1) % stops.len()];
+        let next = stops[next % stops.len()];
+        let span = (next.position - prev.position).rem_euclid(1.0);
+        if span == 0.0 {
+            return prev.color;
+        }
+        interpolation.mix(
+            prev.color,
+            next.color,
+            (t - prev.position).rem_euclid(1.0) / span,
+        )
+    }
+
+    /// the palette sampled at PALETTE_SIZE evenly spaced points, as srgb bytes for the gpu
+    pub(crate) fn texels(&self) -> Vec<[u8; 4]> {
+        let stops = self.sorted_stops();
+        (0..PALETTE_SIZE)
+            .map(|i| {
+                Self::sample(&stops, self.interpolation, i as f32 / PALETTE_SIZE as f32).to_array()
+            })
+            .collect()
+    }
+
+    /// reads a palette saved by save, or imports a fractint .map or ultra fractal .ugr
+    fn load(path: &std::path::Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("map") => Self::from_map(&text, stem),
+            Some("ugr") => Self::from_ugr(&text, stem),
+            _ => text.parse(),
+        }
+    }
+
+    fn save(&self, path: &std::path::Path) -> Result<(), String> {
+        std::fs::write(path, self.to_string()).map_err(|e| e.to_string())
+    }
+
+    /// a fractint map, which is a line of "r g b" per color, usually 256 of them.
+    /// anything after the third number is a comment.
+    fn from_map(text: &str, name: String) -> Result<Self, String> {
+        let colors = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                let mut channels = line.split_whitespace().map(|channel| channel.parse::<u8>());
+                match (channels.next(), channels.next(), channels.next()) {
+                    (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => Ok(egui::Color32::from_rgb(r, g, b)),
+                    _ => Err(format!("line {} isn't an r g b color", i + 1)),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if colors.is_empty() {
+            return Err("the map has no colors".to_string());
+        }
+        let len = colors.len();
+        Ok(Self {
+            name,
+            stops: colors
+                .into_iter()
+                .enumerate()
+                .map(|(i, color)| ColorStop {
+                    position: i as f32 / len as f32,
+                    color,
+                })
+                .collect(),
+            interpolation: Interpolation::LinearSrgb,
+        })
+    }
+
+    /// the first gradient in an ultra fractal gradient file, which looks like
+    /// `name { gradient: title="..." index=0 color=123 index=200 color=456 ... }`
+    /// where the colors are 0xbbggrr
+    fn from_ugr(text: &str, name: String) -> Result<Self, String> {
+        let gradient = text
+            .split_once('{')
+            .and_then(|(_, rest)| rest.split_once('}'))
+            .map(|(gradient, _)| gradient)
+            .ok_or("there's no gradient in the file")?;
+        let name = gradient
+            .split_once("title=\"")
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map_or(name, |(title, _)| title.to_string());
+        let mut stops = vec![];
+        let mut index = None;
+        for token in gradient.split_whitespace() {
+            if let Some(value) = token.strip_prefix("index=") {
+                index = Some(value.parse::<i64>().map_err(|e| e.to_string())?);
+            } else if let Some(value) = token.strip_prefix("color=") {
+                let color = value.parse::<u32>().map_err(|e| e.to_string())?;
+                let index = index.take().ok_or("a color is missing its index")?;
+                let [r, g, b, _] = color.to_le_bytes();
+                stops.push(ColorStop {
+                    position: index.rem_euclid(UGR_SIZE) as f32 / UGR_SIZE as f32,
+                    color: egui::Color32::from_rgb(r, g, b),
+                });
+            }
+        }
+        if stops.is_empty() {
+            return Err("the gradient has no colors".to_string());
+        }
+        Ok(Self {
+            name,
+            stops,
+            interpolation: Interpolation::LinearSrgb,
+        })
+    }
+
+    /// the palette editor window.
+    /// returns whether the palette changed.
+    pub(crate) fn editor_ui(
+        &mut self,
+        ctx: &egui::Context,
+        title: String,
+        open: &mut bool,
+    ) -> bool {
+        let old = self.clone();
+        egui::Window::new(title).open(open).show(ctx, |ui| {
+            let selected_id = ui.make_persistent_id("selected_stop");
+            let mut selected = ui
+                .data(|data| data.get_temp::<usize>(selected_id))
+                .filter(|selected| *selected < self.stops.len());
+
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.name);
+                ui.label("name");
+            });
+            egui::ComboBox::from_label("interpolation")
+                .selected_text(self.interpolation.name())
+                .show_ui(ui, |ui| {
+                    for option in Interpolation::ALL {
+                        ui.selectable_value(&mut self.interpolation, option, option.name());
+                    }
+                });
+
+            // preview strip, double click it to add a stop
+            let width = ui.available_width().max(PALETTE_SIZE as f32);
+            let (strip, response) =
+                ui.allocate_exact_size(egui::Vec2::new(width, 32.0), egui::Sense::click());
+            let stops = self.sorted_stops();
+            for i in 0..PALETTE_SIZE {
+                let t = i as f32 / PALETTE_SIZE as f32;
+                let x = strip.left() + t * strip.width();
+                ui.painter().rect_filled(
+                    egui::Rect::from_min_max(
+                        egui::Pos2::new(x, strip.top()),
+                        egui::Pos2::new(
+                            x + strip.width() / PALETTE_SIZE as f32 + 0.5,
+                            strip.bottom(),
+                        ),
+                    ),
+                    0.0,
+                    Self::sample(&stops, self.interpolation, t),
+                );
+            }
+            if response.double_clicked()
+                && let Some(pos) = response.interact_pointer_pos()
+            {
+                let position = ((pos.x - strip.left()) / strip.width()).clamp(0.0, 1.0) % 1.0;
+                self.stops.push(ColorStop {
+                    position,
+                    color: Self::sample(&stops, self.interpolation, position),
+                });
+                selected = Some(self.stops.len() - 1);
+            }
+
+            // draggable handles under the strip
+            let (handles, _) =
+                ui.allocate_exact_size(egui::Vec2::new(width, 16.0), egui::Sense::hover());
+            for (i, stop) in self.stops.iter_mut().enumerate() {
+                let x = handles.left() + stop.position * handles.width();
+                let rect = egui::Rect::from_center_size(
+                    egui::Pos2::new(x, handles.center().y),
+                    egui::Vec2::new(10.0, 14.0),
+                );
+                let response =
+                    ui.interact(rect, selected_id.with(i), egui::Sense::click_and_drag());
+                if response.dragged() {
+                    stop.position =
+                        (stop.position + response.drag_delta().x / handles.width()).rem_euclid(1.0);
+                }
+                if response.clicked() || response.drag_started() {
+                    selected = Some(i);
+                }
+                let stroke = if selected == Some(i) {
+                    egui::Stroke::new(2.0, ui.visuals().strong_text_color())
+                } else {
+                    egui::Stroke::new(1.0, ui.visuals().weak_text_color())
+                };
+                ui.painter()
+                    .rect(rect, 2.0, stop.color, stroke, egui::StrokeKind::Inside);
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("add stop").clicked() {
+                    // in the middle of the biggest gap
+                    let (position, _) = stops
+                        .iter()
+                        .zip(stops.iter().cycle().skip(1))
+                        .map(|(a, b)| {
+                            let gap = (b.position - a.position).rem_euclid(1.0);
+                            let gap = if gap == 0.0 { 1.0 } else { gap };
+                            ((a.position + gap / 2.0) % 1.0, gap)
+                        })
+                        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                        .expect("there's always a stop");
+                    self.stops.push(ColorStop {
+                        position,
+                        color: Self::sample(&stops, self.interpolation, position),
+                    });
+                    selected = Some(self.stops.len() - 1);
+                }
+                if let Some(i) = selected
+                    && ui
+                        .add_enabled(self.stops.len() > 1, egui::Button::new("remove stop"))
+                        .clicked()
+                {
+                    self.stops.remove(i);
+                    selected = None;
+                }
+            });
+            if let Some(i) = selected {
+                let stop = &mut self.stops[i];
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_srgba(&mut stop.color);
+                    ui.add(
+                        egui::DragValue::new(&mut stop.position)
+                            .range(0.0..=0.999)
+                            .speed(0.001),
+                    );
+                    ui.label("selected stop");
+                });
+            }
+            match selected {
+                Some(selected) => ui.data_mut(|data| data.insert_temp(selected_id, selected)),
+                None => ui.data_mut(|data| data.remove::<usize>(selected_id)),
+            }
+
+            // saving and loading
+            let path_id = ui.make_persistent_id("path");
+            let status_id = ui.make_persistent_id("status");
+            let mut path = ui
+                .data_mut(|data| data.get_temp::<String>(path_id))
+                .unwrap_or_else(|| format!("{}.palette", self.name));
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut path);
+                ui.label("path");
+            });
+            ui.horizontal(|ui| {
+                let status = if ui.button("save").clicked() {
+                    Some(self.save(path.as_ref()).map(|()| format!("saved {path}")))
+                } else if ui
+                    .button("load")
+                    .on_hover_text("also imports fractint .map and ultra fractal .ugr files")
+                    .clicked()
+                {
+                    Some(Self::load(path.as_ref()).map(|palette| {
+                        *self = palette;
+                        format!("loaded {path}")
+                    }))
+                } else {
+                    None
+                };
+                if let Some(status) = status {
+                    ui.data_mut(|data| data.insert_temp(status_id, status.unwrap_or_else(|e| e)));
+                }
+            });
+            if let Some(status) = ui.data(|data| data.get_temp::<String>(status_id)) {
+                ui.label(status);
+            }
+            ui.data_mut(|data| data.insert_temp(path_id, path));
+        });
+        *self != old
+    }
+}
+
+/// the format palettes are saved in, which is
+/// ```text
+/// name <name>
+/// interpolation <linear_srgb or oklab>
+/// <position> <rrggbb>
+/// ...
+/// ```
+/// with # comments
+impl std::fmt::Display for Palette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# fractal_egui palette")?;
+        writeln!(f, "name {}", self.name)?;
+        writeln!(f, "interpolation {}", self.interpolation.key())?;
+        for stop in self.sorted_stops() {
+            let [r, g, b, _] = stop.color.to_array();
+            writeln!(f, "{} {:02x}{:02x}{:02x}", stop.position, r, g, b)?;
+        }
+        Ok(())
+    }
+}
+impl std::str::FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut name = String::new();
+        let mut interpolation = Interpolation::LinearSrgb;
+        let mut stops = vec![];
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = || format!("couldn't read line {}: {line}", i + 1);
+            let (key, value) = line.split_once(' ').ok_or_else(error)?;
+            let value = value.trim();
+            if key == "name" {
+                name = value.to_string();
+            } else if key == "interpolation" {
+                interpolation = Interpolation::ALL
+                    .into_iter()
+                    .find(|interpolation| interpolation.key() == value)
+                    .ok_or_else(error)?;
+            } else {
+                let position = key
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|position| (0.0..1.0).contains(position))
+                    .ok_or_else(error)?;
+                let color = u32::from_str_radix(value, 16)
+                    .ok()
+                    .filter(|_| value.len() == 6)
+                    .ok_or_else(error)?;
+                let [b, g, r, _] = color.to_le_bytes();
+                stops.push(ColorStop {
+                    position,
+                    color: egui::Color32::from_rgb(r, g, b),
+                });
+            }
+        }
+        if stops.is_empty() {
+            return Err("the palette has no colors".to_string());
+        }
+        Ok(Self {
+            name,
+            stops,
+            interpolation,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stop(position: f32, r: u8, g: u8, b: u8) -> ColorStop {
+        ColorStop {
+            position,
+            color: egui::Color32::from_rgb(r, g, b),
+        }
+    }
+
+    #[test]
+    fn native_round_trips() {
+        let palette = Palette::default();
+        assert_eq!(palette.to_string().parse::<Palette>(), Ok(palette));
+
+        // saving sorts the stops
+        let palette = Palette {
+            name: "two words".to_string(),
+            stops: vec![
+                stop(0.75, 1, 2, 3),
+                stop(0.1, 255, 0, 128),
+                stop(0.0, 0, 0, 0),
+            ],
+            interpolation: Interpolation::LinearSrgb,
+        };
+        let parsed = palette.to_string().parse::<Palette>().unwrap();
+        assert_eq!(parsed.name, "two words");
+        assert_eq!(parsed.interpolation, Interpolation::LinearSrgb);
+        assert_eq!(parsed.stops, palette.sorted_stops());
+    }
+
+    #[test]
+    fn native_malformed_lines() {
+        let error = "name x\n0 ff0000\n1.5 00ff00\n"
+            .parse::<Palette>()
+            .unwrap_err();
+        assert!(error.contains("line 3"), "{error}");
+        assert!("0 ff00\n".parse::<Palette>().is_err());
+        assert!("0 gg0000\n".parse::<Palette>().is_err());
+        assert!(
+            "interpolation cubic\n0 ff0000\n"
+                .parse::<Palette>()
+                .is_err()
+        );
+        assert!("# only a comment\nname x\n".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn map_import() {
+        let map = "0 0 0 black\n255 128 0\n\n  0 0 255   blue\n";
+        let palette = Palette::from_map(map, "fire".to_string()).unwrap();
+        assert_eq!(palette.name, "fire");
+        assert_eq!(
+            palette.stops,
+            [
+                stop(0.0, 0, 0, 0),
+                stop(1.0 / 3.0, 255, 128, 0),
+                stop(2.0 / 3.0, 0, 0, 255),
+            ]
+        );
+
+        // blank lines still count towards the line number
+        let error = Palette::from_map("0 0 0\n\n1 2\n", String::new()).unwrap_err();
+        assert!(error.contains("line 3"), "{error}");
+        assert!(Palette::from_map("256 0 0\n", String::new()).is_err());
+        assert!(Palette::from_map("\n  \n", String::new()).is_err());
+    }
+
+    #[test]
+    fn ugr_import() {
+        let ugr = r#"
+sunset {
+gradient:
+  title="Sunset" smooth=yes
+  index=0 color=255
+  index=200 color=16711680
+  index=-100 color=65280
+}
+second {
+gradient:
+  index=0 color=0
+}
+"#;
+        let palette = Palette::from_ugr(ugr, "file".to_string()).unwrap();
+        assert_eq!(palette.name, "Sunset");
+        // colors are 0xbbggrr and indices wrap around 400
+        assert_eq!(
+            palette.stops,
+            [
+                stop(0.0, 255, 0, 0),
+                stop(0.5, 0, 0, 255),
+                stop(0.75, 0, 255, 0)
+            ]
+        );
+
+        let untitled = Palette::from_ugr("x { gradient: index=0 color=0 }", "file".to_string());
+        assert_eq!(untitled.unwrap().name, "file");
+        assert!(Palette::from_ugr("no braces", String::new()).is_err());
+        assert!(Palette::from_ugr("x { gradient: color=0 }", String::new()).is_err());
+        assert!(Palette::from_ugr("x { gradient: index=a color=0 }", String::new()).is_err());
+        assert!(Palette::from_ugr("x { gradient: title=\"t\" }", String::new()).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::{Arc, Mutex};
+
+use eframe::wgpu;
+
+/// set by map_async's callback once the map is done or failed
+pub(crate) type MapResult = Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>;
+
+/// starts mapping all of buffer for reading, for the caller to check on with the result
+pub(crate) fn map_read(buffer: &wgpu::Buffer) -> MapResult {
+    let result = MapResult::default();
+    let callback_result = result.clone();
+    buffer
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, move |map_result| {
+            *callback_result.lock().unwrap() = Some(map_result);
+        });
+    result
+}
+
+/// a storage buffer the gpu fills, read back to the cpu a frame or so later without blocking
+pub(crate) struct Readback {
+    pub(crate) buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    state: ReadbackState,
+    /// whether buffer has data we haven't read back yet
+    stale: bool,
+}
+
+enum ReadbackState {
+    Idle,
+    /// the copy is in a command encoder that hasn't been submitted yet
+    Encoded,
+    /// readback_buffer is being mapped
+    Mapping(MapResult),
+}
+
+impl Readback {
+    pub(crate) fn new(device: &wgpu::Device, label: &str, size: u64) -> Self {
+        Self {
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("{label}_buffer")),
+                size,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("{label}_readback_buffer")),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            state: ReadbackState::Idle,
+            stale: false,
+        }
+    }
+
+    /// zeroes buffer before a pass fills it again
+    pub(crate) fn clear(&mut self, command_encoder: &mut wgpu::CommandEncoder) {
+        command_encoder.clear_buffer(&self.buffer, 0, None);
+        self.stale = true;
+    }
+
+    /// copies buffer to the readback buffer if it has something newer and the last readback
+    /// is done with it. returns whether it did, in which case call start after submitting.
+    pub(crate) fn encode(&mut self, command_encoder: &mut wgpu::CommandEncoder) -> bool {
+        if !self.stale || !matches!(self.state, ReadbackState::Idle) {
+            return false;
+        }
+        self.stale = false;
+        command_encoder.copy_buffer_to_buffer(
+            &self.buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            self.buffer.size(),
+        );
+        self.state = ReadbackState::Encoded;
+        true
+    }
+
+    /// maps the readback buffer if a copy was encoded since the last call.
+    /// a map that's still going is left alone, since mapping it again fails.
+    pub(crate) fn start(&mut self) {
+        if !matches!(self.state, ReadbackState::Encoded) {
+            return;
+        }
+        self.state = ReadbackState::Mapping(map_read(&self.readback_buffer));
+    }
+
+    /// whether there's data in buffer that hasn't come back yet
+    pub(crate) fn is_pending(&self) -> bool {
+        self.stale || !matches!(self.state, ReadbackState::Idle)
+    }
+
+    /// reads the data if the readback finished.
+    /// a failed map drops that readback, and the next one goes ahead as usual.
+    pub(crate) fn poll<T>(
+        &mut self,
+        device: &wgpu::Device,
+        read: impl FnOnce(&[u8]) -> T,
+    ) -> Option<Result<T, wgpu::BufferAsyncError>> {
+        let ReadbackState::Mapping(result) = &self.state else {
+            return None;
+        };
+        device.poll(wgpu::Maintain::Poll);
+        let map_result = result.lock().unwrap().take()?;
+        self.state = ReadbackState::Idle;
+        Some(map_result.map(|()| {
+            let data = read(&self.readback_buffer.slice(..).get_mapped_range());
+            self.readback_buffer.unmap();
+            data
+        }))
+    }
+}
@@ -0,0 +1,382 @@
+use std::{collections::HashMap, sync::Arc};
+
+use eframe::{
+    egui::{self, mutex::Mutex},
+    egui_wgpu,
+    wgpu::{self, util::DeviceExt},
+};
+
+use crate::{Params, map_read, split_f64};
+
+/// what the fractals render to, and what their pipelines are made for
+pub(crate) const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// the gpu state every fractal shares, made once by the app.
+/// the fractal type and all the options are params, so every fractal runs the same shader
+/// and the only thing the pipelines differ by is how the iteration pass runs.
+/// each fractal just has its own buffers, textures and bind group.
+pub(crate) struct FractalRenderer {
+    pub(crate) device: wgpu::Device,
+    pub(crate) queue: wgpu::Queue,
+    pub(crate) egui_renderer: Arc<egui::mutex::RwLock<egui_wgpu::Renderer>>,
+    /// for the benchmark's results
+    pub(crate) adapter_name: String,
+    shader_module: wgpu::ShaderModule,
+    pub(crate) bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    pub(crate) iterate_pipeline: wgpu::RenderPipeline,
+    pub(crate) color_pipeline: wgpu::RenderPipeline,
+    /// by workgroup size, made when the compute backend first uses it
+    compute_pipelines: Mutex<HashMap<[u32; 2], wgpu::ComputePipeline>>,
+    /// whether the double-single precision passed its self-test, otherwise it isn't offered
+    pub(crate) double_single_works: bool,
+}
+impl FractalRenderer {
+    pub(crate) fn new(render_state: &egui_wgpu::RenderState) -> Self {
+        let device = &render_state.device;
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("shader.wgsl"),
+            // f64 types don't even parse without SHADER_F64, so they get their own file
+            source: wgpu::ShaderSource::Wgsl(
+                [
+                    include_str!("shader.wgsl"),
+                    if device.features().contains(wgpu::Features::SHADER_F64) {
+                        include_str!("shader_f64.wgsl")
+                    } else {
+                        include_str!("shader_f64_stub.wgsl")
+                    },
+                ]
+                .concat()
+                .into(),
+            ),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("render_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX
+                        | wgpu::ShaderStages::FRAGMENT
+                        | wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(
+                            wgpu::BufferSize::new(std::mem::size_of::<Params>() as u64).unwrap(),
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D1,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 8,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 9,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 10,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("render_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        // the iteration pass only writes the orbit buffer, so its color writes are masked out
+        let iterate_pipeline = Self::create_render_pipeline(
+            device,
+            &pipeline_layout,
+            &shader_module,
+            "fragment_iterate",
+            TEXTURE_FORMAT,
+            wgpu::ColorWrites::empty(),
+        );
+        let color_pipeline = Self::create_render_pipeline(
+            device,
+            &pipeline_layout,
+            &shader_module,
+            "fragment_color",
+            TEXTURE_FORMAT,
+            wgpu::ColorWrites::all(),
+        );
+
+        let double_single_works =
+            Self::test_double_single(device, &render_state.queue, &shader_module);
+
+        Self {
+            device: device.clone(),
+            queue: render_state.queue.clone(),
+            egui_renderer: render_state.renderer.clone(),
+            adapter_name: render_state.adapter.get_info().name,
+            shader_module,
+            bind_group_layout,
+            pipeline_layout,
+            iterate_pipeline,
+            color_pipeline,
+            compute_pipelines: Mutex::new(HashMap::new()),
+            double_single_works,
+        }
+    }
+
+    /// runs ds_self_test in shader.wgsl on a few numbers and checks the sums and products
+    /// are as close as double-single should get them. a compiler that reorders the float
+    /// operations loses the low halves, which puts them off by about an f32's epsilon instead.
+    /// blocks on the gpu, which is fine once at startup.
+    fn test_double_single(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shader_module: &wgpu::ShaderModule,
+    ) -> bool {
+        let pairs = [
+            (1.0 / 3.0, 1.0 / 7.0),
+            (std::f64::consts::PI, -std::f64::consts::E),
+            (-std::f64::consts::SQRT_2, 1e-3 / 3.0),
+            (0.1, 1.0 + 1e-9),
+        ];
+        let values: Vec<[f32; 2]> = pairs
+            .iter()
+            .flat_map(|&(a, b)| [split_f64(a), split_f64(b)])
+            .map(|(hi, lo)| [hi, lo])
+            .collect();
+        let size = std::mem::size_of_val(values.as_slice()) as u64;
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("ds_test_values"),
+            contents: bytemuck::cast_slice(&values),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("ds_test_readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // laid out from the shader, since it only uses its own binding
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("ds_self_test"),
+            layout: None,
+            module: shader_module,
+            entry_point: Some("ds_self_test"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("ds_self_test"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 11,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        let mut command_encoder = device.create_command_encoder(&Default::default());
+        {
+            let mut compute_pass = command_encoder.begin_compute_pass(&Default::default());
+            compute_pass.set_pipeline(&pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(pairs.len() as u32, 1, 1);
+        }
+        command_encoder.copy_buffer_to_buffer(&buffer, 0, &readback_buffer, 0, size);
+        queue.submit([command_encoder.finish()]);
+        let mapped = map_read(&readback_buffer);
+        device.poll(wgpu::Maintain::Wait);
+        if !matches!(mapped.lock().unwrap().take(), Some(Ok(()))) {
+            return false;
+        }
+        let results: Vec<[f32; 2]> = readback_buffer
+            .slice(..)
+            .get_mapped_range()
+            .chunks_exact(8)
+            .map(bytemuck::pod_read_unaligned)
+            .collect();
+        readback_buffer.unmap();
+        let close = |[hi, lo]: [f32; 2], expected: f64| {
+            (hi as f64 + lo as f64 - expected).abs() <= expected.abs() * 2.0f64.powi(-40)
+        };
+        pairs
+            .iter()
+            .zip(results.chunks(2))
+            .all(|(&(a, b), result)| close(result[0], a + b) && close(result[1], a * b))
+    }
+
+    /// the compute backend's pipeline for the workgroup size, made the first time it's asked for
+    pub(crate) fn compute_pipeline(&self, workgroup_size: [u32; 2]) -> wgpu::ComputePipeline {
+        self.compute_pipelines
+            .lock()
+            .entry(workgroup_size)
+            .or_insert_with(|| {
+                let constants = [
+                    ("workgroup_size_x".to_owned(), workgroup_size[0] as f64),
+                    ("workgroup_size_y".to_owned(), workgroup_size[1] as f64),
+                ]
+                .into();
+                self.device
+                    .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                        label: Some("compute_iterate"),
+                        layout: Some(&self.pipeline_layout),
+                        module: &self.shader_module,
+                        entry_point: Some("compute_iterate"),
+                        compilation_options: wgpu::PipelineCompilationOptions {
+                            constants: &constants,
+                            zero_initialize_workgroup_memory: false,
+                        },
+                        cache: None,
+                    })
+            })
+            .clone()
+    }
+
+    /// both passes draw the same full screen quad and only differ in the fragment shader
+    fn create_render_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader_module: &wgpu::ShaderModule,
+        fragment_entry_point: &str,
+        format: wgpu::TextureFormat,
+        write_mask: wgpu::ColorWrites,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(fragment_entry_point),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader_module,
+                entry_point: Some("vertex_main"),
+                compilation_options: Default::default(),
+                buffers: &[
+                    // // @location(0) vertex_pos: vec2<f32>,
+                    // wgpu::VertexBufferLayout {
+                    //     array_stride: 4 * 2,
+                    //     step_mode: wgpu::VertexStepMode::Vertex,
+                    //     attributes: &wgpu::vertex_attr_array![0 => Float32x2],
+                    // },
+                    // // @location(1) particle_pos: vec2<f32>,
+                    // wgpu::VertexBufferLayout {
+                    //     array_stride: 4 * 2,
+                    //     step_mode: wgpu::VertexStepMode::Instance,
+                    //     attributes: &wgpu::vertex_attr_array![1 => Float32x2],
+                    // },
+                    // // @location(2) particle_vel: vec2<f32>,
+                    // wgpu::VertexBufferLayout {
+                    //     array_stride: 4 * 2,
+                    //     step_mode: wgpu::VertexStepMode::Instance,
+                    //     attributes: &wgpu::vertex_attr_array![2 => Float32x2],
+                    // },
+                    // // @location(3) particle_species: u32,
+                    // wgpu::VertexBufferLayout {
+                    //     array_stride: 4,
+                    //     step_mode: wgpu::VertexStepMode::Instance,
+                    //     attributes: &wgpu::vertex_attr_array![3 => Uint32],
+                    // },
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader_module,
+                entry_point: Some(fragment_entry_point),
+                compilation_options: Default::default(),
+                // targets: &[Some(config.view_formats[0].into())],
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use eframe::egui;
+
+use crate::Fractal;
+
+/// how many frames in a row a fractal can go without getting any of the budget
+/// before it goes ahead of the main view and the hovered one
+const MAX_SKIPPED_FRAMES: u32 = 30;
+
+/// shares the gpu between all the fractals on screen.
+/// they render once they've all had their ui, in order of priority,
+/// each getting whatever's left of the frame budget.
+pub(crate) struct RenderScheduler {
+    /// in milliseconds, how long all the iteration passes together get each frame
+    frame_budget: f64,
+    /// by fractal id, how many frames in a row the ones with work got none of the budget
+    skipped_frames: HashMap<usize, u32>,
+}
+impl RenderScheduler {
+    pub(crate) fn new() -> Self {
+        Self {
+            frame_budget: 16.0,
+            skipped_frames: HashMap::new(),
+        }
+    }
+
+    /// renders the main view, then the one with the pointer over it, then the rest by how long
+    /// they've waited, until the budget runs out.
+    /// the first always gets at least a row even if it's over budget.
+    /// returns whether any of them still has work, so the app needs to repaint.
+    pub(crate) fn run<'a>(
+        &mut self,
+        main: &'a mut Fractal,
+        windows: impl IntoIterator<Item = &'a mut Fractal>,
+        hovered: Option<usize>,
+    ) -> bool {
+        let main_id = main.id();
+        let mut queue: Vec<&mut Fractal> = std::iter::once(main)
+            .chain(windows)
+            .filter(|fractal| fractal.has_work())
+            .collect();
+        // the ones that are done or closed start over
+        self.skipped_frames
+            .retain(|id, _| queue.iter().any(|fractal| fractal.id() == *id));
+        queue.sort_by_key(|fractal| self.priority(fractal.id(), main_id, hovered));
+
+        let mut budget = self.frame_budget;
+        for fractal in &mut queue {
+            if budget > 0.0 {
+                budget -= fractal.render_to_texture(budget);
+                self.record(fractal.id(), true);
+            } else {
+                self.record(fractal.id(), false);
+            }
+        }
+        queue.iter().any(|fractal| fractal.has_work())
+    }
+
+    /// where a fractal goes in the queue, lowest first
+    fn priority(
+        &self,
+        id: usize,
+        main_id: usize,
+        hovered: Option<usize>,
+    ) -> (u8, std::cmp::Reverse<u32>) {
+        let skipped = self.skipped_frames.get(&id).copied().unwrap_or(0);
+        let rank = if skipped >= MAX_SKIPPED_FRAMES {
+            0
+        } else if id == main_id {
+            1
+        } else if Some(id) == hovered {
+            2
+        } else {
+            3
+        };
+        (rank, std::cmp::Reverse(skipped))
+    }
+
+    /// counts another frame without any of the budget, or starts over once it got some
+    fn record(&mut self, id: usize, rendered: bool) {
+        if rendered {
+            self.skipped_frames.remove(&id);
+        } else {
+            *self.skipped_frames.entry(id).or_default() += 1;
+        }
+    }
+
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.frame_budget, 1.0..=100.0)
+                .logarithmic(true)
+                .suffix(" ms")
+                .text("frame budget"),
+        )
+        .on_hover_text("how much gpu time the time sliced fractals get each frame, between them");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a frame where only the first in the queue fits in the budget, returning which one it was
+    fn frame(
+        scheduler: &mut RenderScheduler,
+        ids: &[usize],
+        main_id: usize,
+        hovered: Option<usize>,
+    ) -> usize {
+        let mut queue = ids.to_vec();
+        queue.sort_by_key(|&id| scheduler.priority(id, main_id, hovered));
+        for (i, &id) in queue.iter().enumerate() {
+            scheduler.record(id, i == 0);
+        }
+        queue[0]
+    }
+
+    #[test]
+    fn starved_fractals_get_promoted() {
+        let mut scheduler = RenderScheduler::new();
+        for _ in 0..MAX_SKIPPED_FRAMES {
+            assert_eq!(frame(&mut scheduler, &[0, 1, 2], 0, Some(2)), 0);
+        }
+        // both waited MAX_SKIPPED_FRAMES, and they take turns from there
+        assert_eq!(frame(&mut scheduler, &[0, 1, 2], 0, Some(2)), 1);
+        assert_eq!(frame(&mut scheduler, &[0, 1, 2], 0, Some(2)), 2);
+        assert_eq!(frame(&mut scheduler, &[0, 1, 2], 0, Some(2)), 0);
+        assert_eq!(scheduler.skipped_frames.get(&1), Some(&2));
+    }
+
+    #[test]
+    fn hovered_goes_ahead_of_the_rest() {
+        let mut scheduler = RenderScheduler::new();
+        scheduler.record(1, false);
+        assert_eq!(frame(&mut scheduler, &[1, 2, 0], 0, Some(2)), 0);
+        assert_eq!(frame(&mut scheduler, &[1, 2], 0, Some(2)), 2);
+        // the longest waiting goes first among the rest
+        assert_eq!(frame(&mut scheduler, &[3, 1], 0, None), 1);
+    }
+}
@@ -3,22 +3,148 @@ struct VertexOutput {
 	@location(0) fragment_position: vec2<f32>,
 }
 
-const CYCLE_DEPTH: u32 = 0xFFFFFFFF;
-
 const FRACTAL_MANDELBROT: u32 = 0;
 const FRACTAL_METABROT: u32 = 1;
 const FRACTAL_JULIA_SET: u32 = 2;
 const FRACTAL_METAJULIA: u32 = 3;
 
-// COLORING_DEPTH_NONE
-// COLORING_CYCLE_NONE
-// COLORING_CYCLE_LOG
-// COLORING_CYCLE_LOG_LOG
+// z^2 + c
+const FORMULA_MANDELBROT: u32 = 0;
+// (|re z| + i|im z|)^2 + c
+const FORMULA_BURNING_SHIP: u32 = 1;
+// conj(z)^2 + c, aka the mandelbar
+const FORMULA_TRICORN: u32 = 2;
+// |re z^2| + i im z^2 + c
+const FORMULA_CELTIC: u32 = 3;
+// |re z^2| - i|im z^2| + c
+const FORMULA_BUFFALO: u32 = 4;
+// re z^2 - 2i|re z| im z + c
+const FORMULA_PERPENDICULAR: u32 = 5;
+
+// what get_depth does its arithmetic in
+const PRECISION_SINGLE: u32 = 0;
+const PRECISION_DOUBLE_SINGLE: u32 = 1;
+const PRECISION_DOUBLE: u32 = 2;
+const PRECISION_PERTURBATION: u32 = 3;
+
+// what the iteration pass found out about a pixel, which is all the coloring pass gets to see.
+// mirrored by ORBIT_SIZE in fractal.rs
+struct Orbit {
+    // the smoothed depth if it escaped, otherwise how far we got
+    depth: f32,
+    // ORBIT_* bits
+    flags: u32,
+    // z when we stopped iterating
+    z: vec2<f32>,
+    // dz/du where u is the position on the screen in units of radius_real,
+    // so it stays in range no matter how deep we zoom
+    derivative: vec2<f32>,
+    // for periodic orbits, the derivative of going around the cycle once
+    multiplier: vec2<f32>,
+    // for periodic orbits, the length of the cycle
+    period: u32,
+    // how close the orbit came to the trap
+    trap: f32,
+    // the smoothed average of the params.average_mode term over the orbit
+    average: f32,
+}
+
+const ORBIT_ESCAPED: u32 = 1;
+// the orbit got caught in a cycle before max_depth
+const ORBIT_PERIODIC: u32 = 2;
+// the perturbation lost its precision partway, so the pixel is only roughly right
+const ORBIT_GLITCHED: u32 = 4;
+
+// what the depth goes through before it's wrapped around the color function
+const COLORING_CYCLE_NONE: u32 = 0;
+const COLORING_CYCLE_LOG: u32 = 1;
+const COLORING_CYCLE_LOG_LOG: u32 = 2;
+// the percentile among the escaped pixels, from the histogram of the last iteration pass
+const COLORING_CYCLE_HISTOGRAM: u32 = 3;
+
+// turns the cycled depth into a color
+const COLORING_FUNCTION_LINEAR: u32 = 0;
+const COLORING_FUNCTION_TURBO: u32 = 1;
+const COLORING_FUNCTION_CYCLIC_TURBO: u32 = 2;
+const COLORING_FUNCTION_RAINBOW: u32 = 3;
+const COLORING_FUNCTION_PALETTE: u32 = 4;
+
+// what the distance estimate does to the exterior
+const DISTANCE_NONE: u32 = 0;
+// black lines along the boundary on white
+const DISTANCE_BOUNDARY: u32 = 1;
+// the depth coloring, fading to black away from the boundary
+const DISTANCE_GLOW: u32 = 2;
+// the depth coloring, fading to transparent away from the boundary
+const DISTANCE_ALPHA: u32 = 3;
+
+// how the points that don't escape are colored,
+// which only sees the cycles found by the single precision
+const INTERIOR_BLACK: u32 = 0;
+const INTERIOR_PERIOD: u32 = 1;
+// by |multiplier|
+const INTERIOR_MULTIPLIER: u32 = 2;
+// by the angle of the multiplier
+const INTERIOR_MULTIPLIER_ANGLE: u32 = 3;
+
+// the orbit trap's shape
+const TRAP_NONE: u32 = 0;
+const TRAP_POINT: u32 = 1;
+const TRAP_LINE: u32 = 2;
+const TRAP_CROSS: u32 = 3;
+const TRAP_CIRCLE: u32 = 4;
+// the cross, but drawn as stalks on top of the usual coloring
+const TRAP_STALKS: u32 = 5;
+
+// what gets averaged over the orbit
+const AVERAGE_NONE: u32 = 0;
+// how much z points in a direction, with stripe_density stripes around the circle
+const AVERAGE_STRIPE: u32 = 1;
+// where |z| lands between the bounds the triangle inequality gives it
+const AVERAGE_TRIANGLE: u32 = 2;
+// how sharply the orbit turns
+const AVERAGE_CURVATURE: u32 = 3;
+
+// where the loaded image gets mapped
+const TEXTURE_NONE: u32 = 0;
+// by the external angle across and the smooth depth along
+const TEXTURE_EXTERIOR: u32 = 1;
+// by the final z
+const TEXTURE_INTERIOR: u32 = 2;
+
+// shades the exterior by the angle of z when it escaped, on top of the rest of the coloring
+const DECOMPOSITION_NONE: u32 = 0;
+// by the sign of imag(z)
+const DECOMPOSITION_BINARY: u32 = 1;
+// by which of decomposition_order sectors z lands in
+const DECOMPOSITION_NARY: u32 = 2;
+// lines along the angles that are multiples of 1 / decomposition_order
+const DECOMPOSITION_FIELD_LINES: u32 = 3;
+// by the angle itself, which is the external angle in the last band
+const DECOMPOSITION_EXTERNAL_ANGLE: u32 = 4;
+
+// what the lighting takes as the height of the surface
+const LIGHTING_NONE: u32 = 0;
+// the smooth depth, compared with the neighbouring pixels
+const LIGHTING_DEPTH: u32 = 1;
+// the distance estimate, whose slope comes straight from the derivative
+const LIGHTING_DISTANCE: u32 = 2;
+// how tight the specular highlight is
+const SHININESS: f32 = 32.0;
+// how lit the parts facing away from the light are
+const AMBIENT: f32 = 0.2;
+
+const PI: f32 = 3.1415926535897932384626433;
+const MAX_F32: f32 = 3.40282347e38;
 
-// COLORING_FUNCTION_LINEAR
-// COLORING_FUNCTION_TURBO
-// COLORING_FUNCTION_CYCLIC_TURBO
-// COLORING_FUNCTION_RAINBOW
+// set when making the compute pipeline, from Backend::Compute
+override workgroup_size_x: u32 = 8;
+override workgroup_size_y: u32 = 8;
+
+// mirrors PALETTE_SIZE in palette.rs
+const PALETTE_SIZE: u32 = 256;
+// mirrors HISTOGRAM_BINS in histogram.rs
+const HISTOGRAM_BINS: u32 = 1024;
 
 struct Params {
     // view params
@@ -26,48 +152,221 @@ struct Params {
     center_imag: f32,
     radius_real: f32,
     radius_imag: f32,
+    // in pixels, for finding a pixel's orbit
+    width: u32,
 
     // shared params
     max_depth: u32,
     escape_radius_2: f32,
 
     // specialized params
+    // which plane we're drawing, the abs variants reuse the mandelbrot and julia planes
     fractal_type: u32,
+    // which function we iterate
+    formula: u32,
+    // z -> z^exponent + c, only used by the mandelbrot and julia formula
+    exponent: f32,
     // either z0 or c depending on fractal_type
     point_real: f32,
     point_imag: f32,
     // used in the the meta-fractals
     sub_fractal_width: u32,
+
+    // precision params
+    precision_mode: u32,
+    // the rest of the center that didn't fit in center_real and center_imag,
+    // for the double-single and double precisions
+    center_real_lo: f32,
+    center_imag_lo: f32,
+    // the rest after those, so the double precision gets all of an f64's bits
+    center_real_lo2: f32,
+    center_imag_lo2: f32,
+    // the rest of the point that didn't fit in point_real and point_imag
+    point_real_lo: f32,
+    point_imag_lo: f32,
+    // with perturbation the view params are relative to the reference orbit
+    // and in units of 2^radius_exponent
+    radius_exponent: i32,
+    reference_orbit_len: u32,
+
+    // panning params
+    // whether the iteration pass copies the pixels that are still on screen from previous_orbits
+    reuse_previous: u32,
+    // how many pixels the view moved since previous_orbits, with y going down
+    shift_x: i32,
+    shift_y: i32,
+
+    // antialiasing params
+    // where in the pixel this sample goes, in pixels with y going down
+    jitter_x: f32,
+    jitter_y: f32,
+    // how many samples are in accumulation, including this one
+    sample_count: u32,
+
+    // incremental depth params
+    // whether the iteration pass picks up the pixels that hadn't escaped from resumes
+    // instead of starting over, after max_depth went up
+    resume_previous: u32,
+    // the pixels that stop at or past this depth count towards stats.changed
+    changed_depth: u32,
+
+    // time slicing params
+    // the rows this slice of the iteration pass covers, which the fragment backend scissors to
+    slice_start: u32,
+    slice_end: u32,
+
+    // coloring params
+    cycle_mode: u32,
+    color_function: u32,
+    // in cycles
+    phase: f32,
+    // cycles per unit of cycled depth
+    density: f32,
+    distance_mode: u32,
+    // in pixels
+    distance_thickness: f32,
+    interior_mode: u32,
+
+    // orbit trap params
+    trap_shape: u32,
+    trap_point_real: f32,
+    trap_point_imag: f32,
+    // (cos, sin) of the angle of the line and the cross
+    trap_direction_real: f32,
+    trap_direction_imag: f32,
+    // of the circle and the stalks
+    trap_radius: f32,
+
+    // averaging params
+    average_mode: u32,
+    stripe_density: f32,
+
+    // decomposition params
+    decomposition_mode: u32,
+    decomposition_order: u32,
+    // how much the decomposition darkens the coloring under it
+    decomposition_strength: f32,
+
+    texture_mode: u32,
+
+    // lighting params
+    lighting_mode: u32,
+    // towards the light, with x real, y imag and z out of the screen
+    light_x: f32,
+    light_y: f32,
+    light_z: f32,
+    specular: f32,
+    // how steep the surface is
+    relief: f32,
 }
 
 @group(0) @binding(0) var<uniform> params: Params;
+// the orbit of the center of the screen, computed on the cpu at full precision
+@group(0) @binding(1) var<storage, read> reference_orbit: array<vec2<f32>>;
+// written by the iteration pass and read by the coloring pass, one per pixel
+@group(0) @binding(2) var<storage, read_write> orbits: array<Orbit>;
+// the palette editor's gradient, sampled evenly
+@group(0) @binding(3) var palette: texture_1d<f32>;
+// how many escaped pixels fall in each bin of histogram_position, counted by the iteration pass
+@group(0) @binding(4) var<storage, read_write> histogram: array<atomic<u32>>;
+// the clipped fraction of escaped pixels before each bin edge, HISTOGRAM_BINS + 1 of them
+@group(0) @binding(5) var<storage, read> cdf: array<f32>;
+// the png the user loaded, or a white pixel
+@group(0) @binding(6) var image: texture_2d<f32>;
+// the orbits from before the last pan, copied before the iteration pass
+@group(0) @binding(7) var<storage, read> previous_orbits: array<Orbit>;
+// the sum of the colors of the samples so far, in linear space
+@group(0) @binding(8) var<storage, read_write> accumulation: array<vec4<f32>>;
+// where each pixel stopped at the last max_depth, only written by the single precision
+@group(0) @binding(9) var<storage, read_write> resumes: array<Resume>;
+// zeroed before each iteration pass and read back after
+@group(0) @binding(10) var<storage, read_write> stats: Stats;
+
+// where get_depth stopped on a pixel that didn't escape, so raising max_depth can carry on from there.
+// mirrored by RESUME_SIZE in fractal.rs
+struct Resume {
+    z: vec2<f32>,
+    derivative: vec2<f32>,
+    // how many iterations it's had
+    depth: u32,
+    trap: f32,
+    average: Average,
+}
 
-fn get_depth(z0_real: f32, z0_imag: f32, c_real: f32, c_imag: f32) -> f32 {
-    var z_real = z0_real;
-    var z_imag = z0_imag;
+fn resume_new(z0: vec2<f32>, dz0: vec2<f32>) -> Resume {
+    return Resume(z0, dz0, 0, trap_distance(z0), average_new(z0));
+}
+
+// mirrored by StatCounts in stats.rs
+struct Stats {
+    // the pixels that escaped or got caught in a cycle at or past params.changed_depth,
+    // leaving out the ones copied from before a pan or a resume
+    changed: atomic<u32>,
+    // the rest are over every pixel, for the auto depth
+    // the pixels that got to max_depth without escaping or getting caught in a cycle
+    unresolved: atomic<u32>,
+    // the pixels that escaped past half of max_depth
+    late: atomic<u32>,
+    // the pixels that escaped past a quarter of max_depth, including the late ones
+    past_quarter: atomic<u32>,
+    // the pixels the perturbation glitched on, for moving the reference
+    glitched: atomic<u32>,
+    // the index of the last of them plus one, so 0 is none
+    glitch_pixel: atomic<u32>,
+}
+
+// dz0 and dc are the derivatives of z0 and c with respect to the pixel's position
+fn get_depth(z0_real: f32, z0_imag: f32, c_real: f32, c_imag: f32, dz0: vec2<f32>, dc: vec2<f32>) -> Orbit {
+    var state = resume_new(vec2(z0_real, z0_imag), dz0);
+    return get_depth_from(&state, c_real, c_imag, dc);
+}
+
+// carries on from state, and leaves it where it stopped if the orbit didn't escape.
+// the cycle detection starts over, which only means it might take a bit longer to notice one.
+fn get_depth_from(state: ptr<function, Resume>, c_real: f32, c_imag: f32, dc: vec2<f32>) -> Orbit {
+    var z_real = (*state).z.x;
+    var z_imag = (*state).z.y;
     var old_real = z_real;
     var old_imag = z_imag;
     var z_real2 = z_real * z_real;
     var z_imag2 = z_imag * z_imag;
+    var derivative = (*state).derivative;
+    var trap = (*state).trap;
+    var average = (*state).average;
     var period_i = 0;
     var period_len = 1;
-    for (var depth: u32 = 0; depth < params.max_depth; depth++) {
+    for (var depth = (*state).depth; depth < params.max_depth; depth++) {
         // TODO: make escape_radius_2 not a constant
         // TODO: does this read from memory each time?
         if (z_real2 + z_imag2 > params.escape_radius_2) {
             // return f32(depth);
             // return f32(depth) - log(log(sqrt(z_real2 + z_imag2)) / log(10.0));
-            return f32(depth) + 2.0 - log(log(z_real2 + z_imag2)) / log(2.0);
+            // return f32(depth) + 2.0 - log(log(z_real2 + z_imag2)) / log(2.0);
+            return Orbit(smooth_depth(depth, z_real2 + z_imag2), ORBIT_ESCAPED, vec2(z_real, z_imag), derivative, vec2(0.0), 0, trap, average_value(average, vec2(z_real, z_imag)));
         }
-        z_imag = (z_real + z_real) * z_imag + c_imag;
-        z_real = z_real2 - z_imag2 + c_real;
+        derivative = step_derivative(vec2(z_real, z_imag), derivative, dc);
+        let z = step(vec2(z_real, z_imag), vec2(z_real2, z_imag2), vec2(c_real, c_imag));
+        z_real = z.x;
+        z_imag = z.y;
         z_real2 = z_real * z_real;
         z_imag2 = z_imag * z_imag;
+        trap = min(trap, trap_distance(z));
+        average_add(&average, z, vec2(c_real, c_imag));
 
         if ((old_real == z_real) && (old_imag == z_imag)) {
             // // TODO: remove
             // return f32(depth);
-            return f32(CYCLE_DEPTH);
+            // old was saved period_i + 1 steps ago, and since the window grows one step at a time
+            // the first time we come back to it is after exactly one trip around the cycle
+            let period = u32(period_i + 1);
+            // the derivative of going around it once
+            var multiplier = vec2(1.0, 0.0);
+            var w = z;
+            for (var i: u32 = 0; i < period; i++) {
+                multiplier = step_derivative(w, multiplier, vec2(0.0));
+                w = step(w, w * w, vec2(c_real, c_imag));
+            }
+            return Orbit(f32(depth), ORBIT_PERIODIC, z, derivative, multiplier, period, trap, average_value(average, z));
         }
 
         period_i += 1;
@@ -78,7 +377,380 @@ fn get_depth(z0_real: f32, z0_imag: f32, c_real: f32, c_imag: f32) -> f32 {
             old_imag = z_imag;
         }
     }
-    return f32(params.max_depth);
+    *state = Resume(vec2(z_real, z_imag), derivative, params.max_depth, trap, average);
+    return Orbit(f32(params.max_depth), 0, vec2(z_real, z_imag), derivative, vec2(0.0), 0, trap, average_value(average, vec2(z_real, z_imag)));
+}
+
+// one iteration of the formula.
+// z_squared is (re z^2, im z^2), which get_depth already has for the escape check.
+fn step(z: vec2<f32>, z_squared: vec2<f32>, c: vec2<f32>) -> vec2<f32> {
+    switch params.formula {
+        case FORMULA_BURNING_SHIP: {
+            return vec2(z_squared.x - z_squared.y, 2.0 * abs(z.x * z.y)) + c;
+        }
+        case FORMULA_TRICORN: {
+            return vec2(z_squared.x - z_squared.y, -(z.x + z.x) * z.y) + c;
+        }
+        case FORMULA_CELTIC: {
+            return vec2(abs(z_squared.x - z_squared.y), (z.x + z.x) * z.y) + c;
+        }
+        case FORMULA_BUFFALO: {
+            return vec2(abs(z_squared.x - z_squared.y), -2.0 * abs(z.x * z.y)) + c;
+        }
+        case FORMULA_PERPENDICULAR: {
+            return vec2(z_squared.x - z_squared.y, -2.0 * abs(z.x) * z.y) + c;
+        }
+        default: {
+            if params.exponent == 2.0 {
+                return vec2(z_squared.x - z_squared.y, (z.x + z.x) * z.y) + c;
+            } else {
+                return complex_pow(z.x, z.y, z_squared.x + z_squared.y, params.exponent) + c;
+            }
+        }
+    }
+}
+
+// a running average of the AVERAGE_* term over an orbit
+struct Average {
+    sum: f32,
+    // the newest term, so the average without it can be recovered for the smoothing
+    last: f32,
+    count: u32,
+    z_prev: vec2<f32>,
+    z_prev2: vec2<f32>,
+}
+
+fn average_new(z0: vec2<f32>) -> Average {
+    return Average(0.0, 0.0, 0, z0, z0);
+}
+
+// adds the term for the iteration that just made z
+fn average_add(average: ptr<function, Average>, z: vec2<f32>, c: vec2<f32>) {
+    // negative means there's no term this iteration
+    var term = -1.0;
+    switch params.average_mode {
+        case AVERAGE_STRIPE: {
+            term = 0.5 * sin(params.stripe_density * atan2(z.y, z.x)) + 0.5;
+        }
+        case AVERAGE_TRIANGLE: {
+            // z is f(z_prev) + c, so |f(z_prev)| and |c| bound |z| from both sides.
+            // |f(z_prev)| is |z_prev|^p only for z^p + c, but it's always |z - c|,
+            // which holds for the abs formulas and the meta-fractals' sub-orbits too.
+            let f_norm = length(z - c);
+            let c_norm = length(c);
+            let lo = abs(f_norm - c_norm);
+            let hi = f_norm + c_norm;
+            if hi > lo {
+                term = (length(z) - lo) / (hi - lo);
+            }
+        }
+        case AVERAGE_CURVATURE: {
+            let a = z - (*average).z_prev;
+            let b = (*average).z_prev - (*average).z_prev2;
+            if (*average).count > 0 && dot(b, b) > 0.0 {
+                // the angle of a / b
+                let q = complex_mul(a, vec2(b.x, -b.y));
+                term = abs(atan2(q.y, q.x)) / PI;
+            }
+        }
+        default: {}
+    }
+    if term >= 0.0 {
+        (*average).sum += term;
+        (*average).last = term;
+        (*average).count += 1;
+    }
+    (*average).z_prev2 = (*average).z_prev;
+    (*average).z_prev = z;
+}
+
+// the average, smoothly interpolated between with and without the last term
+// by how far through the last iteration the orbit escaped, so there's no banding.
+// z is the last z.
+fn average_value(average: Average, z: vec2<f32>) -> f32 {
+    if average.count == 0 {
+        return 0.0;
+    }
+    let with_last = average.sum / f32(average.count);
+    if average.count == 1 || abs(params.exponent) <= 1.0 {
+        return with_last;
+    }
+    let without_last = (average.sum - average.last) / f32(average.count - 1);
+    // 1 just past the escape radius and 0 at its |p|-th power, and the ratio is the same squared
+    let t = saturate(1.0 + log(log(params.escape_radius_2) / log(dot(z, z))) / log(abs(params.exponent)));
+    return mix(without_last, with_last, t);
+}
+
+// how far z is from the orbit trap
+fn trap_distance(z: vec2<f32>) -> f32 {
+    let offset = z - vec2(params.trap_point_real, params.trap_point_imag);
+    // rotated so the line and cross are along the axes
+    let direction = vec2(params.trap_direction_real, params.trap_direction_imag);
+    let along = dot(offset, direction);
+    let across = dot(offset, vec2(-direction.y, direction.x));
+    switch params.trap_shape {
+        case TRAP_POINT: {
+            return length(offset);
+        }
+        case TRAP_LINE: {
+            return abs(across);
+        }
+        case TRAP_CROSS, TRAP_STALKS: {
+            return min(abs(along), abs(across));
+        }
+        case TRAP_CIRCLE: {
+            return abs(length(offset) - params.trap_radius);
+        }
+        default: {
+            return MAX_F32;
+        }
+    }
+}
+
+// the derivative of z^p + c, which only approximates the abs formulas,
+// but it's close enough for coloring
+fn step_derivative(z: vec2<f32>, derivative: vec2<f32>, dc: vec2<f32>) -> vec2<f32> {
+    if params.exponent == 2.0 {
+        return 2.0 * complex_mul(z, derivative) + dc;
+    }
+    let z_p_1 = complex_pow(z.x, z.y, dot(z, z), params.exponent - 1.0);
+    return params.exponent * complex_mul(z_p_1, derivative) + dc;
+}
+
+// z^p for any real p, with 0^p = 0 even for negative p,
+// which makes the negative multibrots start at z1 = c instead of infinity
+fn complex_pow(z_real: f32, z_imag: f32, z_norm2: f32, p: f32) -> vec2<f32> {
+    if z_norm2 == 0.0 {
+        return vec2(0.0, 0.0);
+    }
+    let r = pow(z_norm2, 0.5 * p);
+    let theta = p * atan2(z_imag, z_real);
+    return r * vec2(cos(theta), sin(theta));
+}
+
+// the fractional depth of an escaped orbit.
+// far from the set |z| grows like |z|^p each iteration,
+// so we can tell how far through the iteration we escaped from log(log|z|).
+// for p < -1 it's the step out from near 0 that escapes, which multiplies log|z| by p,
+// so the same goes with |p|.
+// nothing grows like that for -1 <= p <= 1, so we just band those.
+fn smooth_depth(depth: u32, z_norm2: f32) -> f32 {
+    let p = abs(params.exponent);
+    if p <= 1.0 {
+        return f32(depth);
+    }
+    return f32(depth) + 1.0 - log(0.5 * log(z_norm2)) / log(p);
+}
+
+fn complex_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+// scales the mantissa of a mantissa * 2^exponent number back to around 1,
+// so it can keep shrinking or growing without leaving the range of an f32
+fn renormalize(mantissa: ptr<function, vec2<f32>>, exponent: ptr<function, i32>) {
+    let largest = max(abs((*mantissa).x), abs((*mantissa).y));
+    if largest == 0.0 {
+        return;
+    }
+    let shift = frexp(largest).exp;
+    *mantissa = ldexp(*mantissa, vec2(-shift));
+    *exponent += shift;
+}
+
+// get_depth for z^2 + c, but iterating the difference from the reference orbit.
+// the difference is stored as d * 2^e so it can be much smaller than an f32,
+// which is what lets us zoom until f64 runs out.
+// dz0 and dc are the offsets of this pixel's z0 and c from the reference's.
+// the derivatives of z0 and c with respect to the pixel's position are in units of 2^radius_exponent,
+// and the derivative gets its own exponent since it can overflow an f32 before it's useful.
+fn get_depth_perturbed(dz0: vec2<f32>, dz0_exponent: i32, dc: vec2<f32>, dc_exponent: i32, derivative_z0: vec2<f32>, derivative_c: vec2<f32>) -> Orbit {
+    var d = dz0;
+    var e = dz0_exponent;
+    renormalize(&d, &e);
+    var derivative = derivative_z0;
+    var derivative_exponent = params.radius_exponent;
+    renormalize(&derivative, &derivative_exponent);
+    var m: u32 = 0;
+    var flags = 0u;
+    var z = reference_orbit[0] + ldexp(d, vec2(e));
+    var trap = MAX_F32;
+    var average = average_new(z);
+    // the averages only need a rough c
+    var c = vec2(params.point_real, params.point_imag);
+    if params.fractal_type == FRACTAL_MANDELBROT && params.reference_orbit_len > 1 {
+        c = reference_orbit[1] - complex_mul(reference_orbit[0], reference_orbit[0]) + ldexp(dc, vec2(dc_exponent));
+    }
+    for (var depth: u32 = 0; depth < params.max_depth; depth++) {
+        var z_ref = reference_orbit[m];
+        let delta = ldexp(d, vec2(e));
+        z = z_ref + delta;
+        trap = min(trap, trap_distance(z));
+        if depth > 0 {
+            average_add(&average, z, c);
+        }
+        let z_norm2 = dot(z, z);
+        if z_norm2 > params.escape_radius_2 {
+            return Orbit(smooth_depth(depth, z_norm2), flags | ORBIT_ESCAPED, z, ldexp(derivative, vec2(derivative_exponent)), vec2(0.0), 0, trap, average_value(average, z));
+        }
+
+        derivative = 2.0 * complex_mul(z, derivative)
+            + ldexp(derivative_c, vec2(params.radius_exponent - derivative_exponent));
+        renormalize(&derivative, &derivative_exponent);
+
+        // rebasing, aka re-referencing to the start of the orbit.
+        // once z is closer to the start of the reference than to the reference itself,
+        // the difference has lost its precision, which is what causes glitches.
+        // this also lets pixels outlive a reference that escaped early.
+        let rebased = z - reference_orbit[0];
+        let closer = dot(rebased, rebased) < dot(delta, delta);
+        if closer || m + 1 >= params.reference_orbit_len {
+            // running off the end without being closer leaves f32 rounding of z in the difference,
+            // which is far bigger than a pixel at a deep zoom
+            if !closer {
+                flags |= ORBIT_GLITCHED;
+            }
+            z_ref = reference_orbit[0];
+            d = rebased;
+            e = 0;
+            renormalize(&d, &e);
+            m = 0;
+        }
+
+        // (Z + d)^2 + (C + dc) - (Z^2 + C) = 2Zd + d^2 + dc
+        d = 2.0 * complex_mul(z_ref, d)
+            + ldexp(complex_mul(d, d), vec2(e))
+            + ldexp(dc, vec2(dc_exponent - e));
+        renormalize(&d, &e);
+        m += 1;
+    }
+    return Orbit(f32(params.max_depth), flags, z, ldexp(derivative, vec2(derivative_exponent)), vec2(0.0), 0, trap, average_value(average, z));
+}
+
+// double-single arithmetic, an f32 pair (hi, lo) with hi + lo being the number,
+// which gets about 48 bits of mantissa without needing f64 support.
+// these rely on the compiler not reassociating the float operations, which ds_self_test checks.
+// https://andrewthall.org/papers/df64_qf128.pdf
+
+fn ds_two_sum(a: f32, b: f32) -> vec2<f32> {
+    let s = a + b;
+    let v = s - a;
+    let e = (a - (s - v)) + (b - v);
+    return vec2(s, e);
+}
+
+// ds_two_sum but requires |a| >= |b|
+fn ds_quick_two_sum(a: f32, b: f32) -> vec2<f32> {
+    let s = a + b;
+    let e = b - (s - a);
+    return vec2(s, e);
+}
+
+fn ds_split(a: f32) -> vec2<f32> {
+    // 2^12 + 1
+    let t = 4097.0 * a;
+    let hi = t - (t - a);
+    return vec2(hi, a - hi);
+}
+
+fn ds_two_prod(a: f32, b: f32) -> vec2<f32> {
+    let p = a * b;
+    let a_split = ds_split(a);
+    let b_split = ds_split(b);
+    let e = ((a_split.x * b_split.x - p) + a_split.x * b_split.y + a_split.y * b_split.x) + a_split.y * b_split.y;
+    return vec2(p, e);
+}
+
+fn ds_add(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    let s = ds_two_sum(a.x, b.x);
+    let t = ds_two_sum(a.y, b.y);
+    let u = ds_quick_two_sum(s.x, s.y + t.x);
+    return ds_quick_two_sum(u.x, u.y + t.y);
+}
+
+fn ds_sub(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return ds_add(a, -b);
+}
+
+fn ds_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    let p = ds_two_prod(a.x, b.x);
+    return ds_quick_two_sum(p.x, p.y + (a.x * b.y + a.y * b.x));
+}
+
+fn ds_abs(a: vec2<f32>) -> vec2<f32> {
+    if a.x < 0.0 {
+        return -a;
+    }
+    return a;
+}
+
+// for ds_self_test, which runs once at startup with a layout of its own,
+// so it isn't in the fractals' bind group.
+// holds pairs of inputs, and each pair gets its sum and product written back in its place.
+@group(0) @binding(11) var<storage, read_write> ds_test_values: array<vec2<f32>>;
+
+@compute @workgroup_size(1)
+fn ds_self_test(@builtin(global_invocation_id) id: vec3<u32>) {
+    let a = ds_test_values[2 * id.x];
+    let b = ds_test_values[2 * id.x + 1];
+    ds_test_values[2 * id.x] = ds_add(a, b);
+    ds_test_values[2 * id.x + 1] = ds_mul(a, b);
+}
+
+// get_depth in double-single.
+// z0 and c are (real hi, real lo, imag hi, imag lo).
+// the derivative only needs to be rough so it's tracked in single precision.
+fn get_depth_ds(z0: vec4<f32>, c: vec4<f32>, dz0: vec2<f32>, dc: vec2<f32>) -> Orbit {
+    let c_real = c.xy;
+    let c_imag = c.zw;
+    var z_real = z0.xy;
+    var z_imag = z0.zw;
+    var z_real2 = ds_mul(z_real, z_real);
+    var z_imag2 = ds_mul(z_imag, z_imag);
+    var derivative = dz0;
+    var trap = trap_distance(vec2(z_real.x, z_imag.x));
+    var average = average_new(vec2(z_real.x, z_imag.x));
+    for (var depth: u32 = 0; depth < params.max_depth; depth++) {
+        // the lo parts can't matter for escaping
+        if (z_real2.x + z_imag2.x > params.escape_radius_2) {
+            return Orbit(smooth_depth(depth, z_real2.x + z_imag2.x), ORBIT_ESCAPED, vec2(z_real.x, z_imag.x), derivative, vec2(0.0), 0, trap, average_value(average, vec2(z_real.x, z_imag.x)));
+        }
+        derivative = step_derivative(vec2(z_real.x, z_imag.x), derivative, dc);
+        let cross = ds_mul(z_real, z_imag);
+        let cross2 = ds_add(cross, cross);
+        switch params.formula {
+            case FORMULA_BURNING_SHIP: {
+                z_imag = ds_add(ds_abs(cross2), c_imag);
+                z_real = ds_add(ds_sub(z_real2, z_imag2), c_real);
+            }
+            case FORMULA_TRICORN: {
+                z_imag = ds_add(-cross2, c_imag);
+                z_real = ds_add(ds_sub(z_real2, z_imag2), c_real);
+            }
+            case FORMULA_CELTIC: {
+                z_imag = ds_add(cross2, c_imag);
+                z_real = ds_add(ds_abs(ds_sub(z_real2, z_imag2)), c_real);
+            }
+            case FORMULA_BUFFALO: {
+                z_imag = ds_add(-ds_abs(cross2), c_imag);
+                z_real = ds_add(ds_abs(ds_sub(z_real2, z_imag2)), c_real);
+            }
+            case FORMULA_PERPENDICULAR: {
+                let abs_real = ds_abs(z_real);
+                z_imag = ds_add(-ds_mul(ds_add(abs_real, abs_real), z_imag), c_imag);
+                z_real = ds_add(ds_sub(z_real2, z_imag2), c_real);
+            }
+            default: {
+                z_imag = ds_add(cross2, c_imag);
+                z_real = ds_add(ds_sub(z_real2, z_imag2), c_real);
+            }
+        }
+        z_real2 = ds_mul(z_real, z_real);
+        z_imag2 = ds_mul(z_imag, z_imag);
+        trap = min(trap, trap_distance(vec2(z_real.x, z_imag.x)));
+        average_add(&average, vec2(z_real.x, z_imag.x), vec2(c_real.x, c_imag.x));
+    }
+    return Orbit(f32(params.max_depth), 0, vec2(z_real.x, z_imag.x), derivative, vec2(0.0), 0, trap, average_value(average, vec2(z_real.x, z_imag.x)));
 }
 
 // https://github.com/BenjaminAster/WebGPU-Mandelbrot/blob/main/shader.wgsl
@@ -98,71 +770,414 @@ fn vertex_main(@builtin(vertex_index) i: u32) -> VertexOutput {
 	return VertexOutput(vec4<f32>(position, 0.0, 1.0), position);
 }
 
-fn color_of_depth(depth: f32) -> vec4<f32> {
-    var color: f32;
-    if depth == f32(params.max_depth) {
-        color = 0.0;
-    } else if depth == f32(CYCLE_DEPTH) {
-        color = 0.0;
-    } else if depth == 0 {
-        color = 1.0;
+fn color_of_orbit(orbit: Orbit) -> vec4<f32> {
+    if params.trap_shape == TRAP_NONE {
+        return color_of_orbit_untrapped(orbit);
+    }
+    let trap_color = color_function(fract(orbit.trap * params.density + params.phase));
+    if params.trap_shape != TRAP_STALKS {
+        return trap_color;
+    }
+    // fades in from the edge of the stalk to its middle
+    let stalk = saturate(1.0 - orbit.trap / params.trap_radius);
+    let color = color_of_orbit_untrapped(orbit);
+    return vec4(mix(color.rgb, trap_color.rgb, stalk), color.a);
+}
+
+fn color_of_orbit_untrapped(orbit: Orbit) -> vec4<f32> {
+    if (orbit.flags & ORBIT_ESCAPED) == 0 {
+        return color_of_interior(orbit);
+    }
+    if params.distance_mode == DISTANCE_NONE {
+        return color_of_exterior(orbit);
+    }
+
+    // how far the pixel is from the set, in pixels.
+    // the derivative is in units of radius_real, which is half the width.
+    let z_norm = length(orbit.z);
+    let distance = z_norm * log(z_norm) / length(orbit.derivative) * 0.5 * f32(params.width);
+    // 1 on the boundary and fading to 0 a thickness away
+    let closeness = saturate(params.distance_thickness / distance);
+    switch params.distance_mode {
+        case DISTANCE_BOUNDARY: {
+            let shade = smoothstep(0.0, 1.0, distance / params.distance_thickness);
+            return vec4(shade, shade, shade, 1.0);
+        }
+        case DISTANCE_GLOW: {
+            let color = color_of_exterior(orbit);
+            return vec4(color.rgb * sqrt(closeness), 1.0);
+        }
+        default: {
+            let color = color_of_exterior(orbit);
+            return vec4(color.rgb, closeness);
+        }
+    }
+}
+
+fn color_of_exterior(orbit: Orbit) -> vec4<f32> {
+    var color: vec4<f32>;
+    if params.texture_mode == TEXTURE_EXTERIOR {
+        let angle = atan2(orbit.z.y, orbit.z.x) / (2.0 * PI) + 0.5;
+        color = sample_image(vec2(angle, fract(orbit.depth)));
+    } else if params.average_mode == AVERAGE_NONE {
+        color = color_of_depth(orbit.depth);
     } else {
-        // color = clamp(0.1 * log(f32(depth)), 0.0, 1.0);
-        // color = clamp(0.1 * fract(log(f32(depth))), 0.0, 1.0);
-        
-        // do this so it's cyclic
-        // let t = log(f32(depth));
-        let t = fract(log(f32(depth)));
-        // let t = fract(log(log(f32(depth))));
+        color = color_function(fract(orbit.average * params.density + params.phase));
+    }
+    if params.decomposition_mode == DECOMPOSITION_NONE {
+        return color;
+    }
+    let shade = mix(1.0, decomposition(orbit.z), params.decomposition_strength);
+    return vec4(color.rgb * shade, color.a);
+}
 
-        // if t < 0.95 {
-        //     color = 0.0;
-        // } else {
-        //     color = 1.0;
-        // }
+// in [0, 1], with 0 darkening the most
+fn decomposition(z: vec2<f32>) -> f32 {
+    // in [0, 1), starting from the negative real axis
+    let angle = atan2(z.y, z.x) / (2.0 * PI) + 0.5;
+    let order = f32(params.decomposition_order);
+    switch params.decomposition_mode {
+        case DECOMPOSITION_BINARY: {
+            return select(1.0, 0.0, z.y < 0.0);
+        }
+        case DECOMPOSITION_NARY: {
+            // the golden ratio keeps neighbouring sectors far apart
+            return fract(floor(angle * order) * 0.618034);
+        }
+        case DECOMPOSITION_FIELD_LINES: {
+            // how far the angle is from the nearest line, in [0, 0.5]
+            let offset = abs(fract(angle * order + 0.5) - 0.5);
+            return smoothstep(0.0, 0.1, offset);
+        }
+        default: {
+            return angle;
+        }
+    }
+}
 
-        // basic turbo
-        // return turbo(t, 0.0, 1.0);
+fn color_of_interior(orbit: Orbit) -> vec4<f32> {
+    if params.texture_mode == TEXTURE_INTERIOR {
+        // the interior stays within 2 of the origin
+        return sample_image(orbit.z * 0.25 + 0.5);
+    }
+    if params.interior_mode == INTERIOR_BLACK || (orbit.flags & ORBIT_PERIODIC) == 0 {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    var t: f32;
+    switch params.interior_mode {
+        case INTERIOR_PERIOD: {
+            // the golden ratio keeps nearby periods far apart on the color function
+            t = f32(orbit.period) * 0.618034;
+        }
+        case INTERIOR_MULTIPLIER: {
+            // which is in [0, 1) for attracting cycles
+            t = min(length(orbit.multiplier), 0.999);
+        }
+        default: {
+            t = atan2(orbit.multiplier.y, orbit.multiplier.x) / (2.0 * PI) + 0.5;
+        }
+    }
+    return color_function(fract(t + params.phase));
+}
+
+fn color_of_depth(depth: f32) -> vec4<f32> {
+    if depth == 0 {
+        return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+    }
+    // the smoothing can take the depth under 1 right by the escape radius,
+    // where the logs would go negative
+    var x: f32;
+    switch params.cycle_mode {
+        case COLORING_CYCLE_NONE: {
+            x = depth;
+        }
+        case COLORING_CYCLE_LOG_LOG: {
+            x = log(1.0 + log(max(depth, 1.0)));
+        }
+        case COLORING_CYCLE_HISTOGRAM: {
+            // kept under 1 so the clipped deep pixels don't wrap around to the start
+            x = min(percentile(depth), 0.9999);
+        }
+        default: {
+            x = log(max(depth, 1.0));
+        }
+    }
+    // do this so it's cyclic
+    let t = fract(x * params.density + params.phase);
+    return color_function(t);
+}
 
-        // cyclic turbo
-        // if (t < 0.5) {
-        //     return turbo(t, 0.0, 0.5);
-        // } else {
-        //     return turbo(t, 1.0, 0.5);
-        // }
+// where the depth falls between the bin edges, log spaced since most pixels are shallow
+fn histogram_position(depth: f32) -> f32 {
+    let x = log(1.0 + max(depth, 0.0)) / log(1.0 + f32(params.max_depth));
+    return clamp(x, 0.0, 1.0) * f32(HISTOGRAM_BINS);
+}
 
-        // rainbow
-        return rainbow(t);
+// interpolated between the bin edges, as if the depths were spread evenly in each bin
+fn percentile(depth: f32) -> f32 {
+    let x = histogram_position(depth);
+    let i = min(u32(x), HISTOGRAM_BINS - 1);
+    return mix(cdf[i], cdf[i + 1], x - f32(i));
+}
+
+fn color_function(t: f32) -> vec4<f32> {
+    switch params.color_function {
+        case COLORING_FUNCTION_LINEAR: {
+            return vec4(t, t, t, 1.0);
+        }
+        case COLORING_FUNCTION_TURBO: {
+            return turbo(t, 0.0, 1.0);
+        }
+        case COLORING_FUNCTION_CYCLIC_TURBO: {
+            // up and back down so the ends match
+            if (t < 0.5) {
+                return turbo(t, 0.0, 0.5);
+            } else {
+                return turbo(t, 1.0, 0.5);
+            }
+        }
+        case COLORING_FUNCTION_PALETTE: {
+            return sample_palette(t);
+        }
+        default: {
+            return rainbow(t);
+        }
     }
-    return vec4<f32>(color, color, color, 1.0);
 }
 
+// interpolated by hand since textureSample needs uniform control flow,
+// and this wraps around since palettes are cyclic
+fn sample_palette(t: f32) -> vec4<f32> {
+    let x = t * f32(PALETTE_SIZE);
+    let i = u32(x) % PALETTE_SIZE;
+    let a = textureLoad(palette, i, 0);
+    let b = textureLoad(palette, (i + 1) % PALETTE_SIZE, 0);
+    return mix(a, b, fract(x));
+}
+
+// interpolated by hand like the palette, and wrapped around at the edges
+fn sample_image(uv: vec2<f32>) -> vec4<f32> {
+    let size = textureDimensions(image);
+    let x = fract(uv) * vec2<f32>(size) - 0.5;
+    let i = vec2<i32>(floor(x));
+    let t = x - floor(x);
+    let a = textureLoad(image, wrap_texel(i, size), 0);
+    let b = textureLoad(image, wrap_texel(i + vec2(1, 0), size), 0);
+    let c = textureLoad(image, wrap_texel(i + vec2(0, 1), size), 0);
+    let d = textureLoad(image, wrap_texel(i + vec2(1, 1), size), 0);
+    return mix(mix(a, b, t.x), mix(c, d, t.x), t.y);
+}
+
+fn wrap_texel(texel: vec2<i32>, size: vec2<u32>) -> vec2<u32> {
+    let signed_size = vec2<i32>(size);
+    return vec2<u32>((texel % signed_size + signed_size) % signed_size);
+}
+
+// the iteration pass, which does the expensive part and leaves the result for the coloring pass
 @fragment
-fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    let real: f32 = params.center_real + input.fragment_position.x * params.radius_real;
-    let imag: f32 = params.center_imag + input.fragment_position.y * params.radius_imag;
-    if false {
-        // debug grid
-        return vec4<f32>(fract(real), fract(imag), 0.0, 1.0);
-    } else if params.fractal_type == FRACTAL_MANDELBROT || params.fractal_type == FRACTAL_JULIA_SET  {
-        var depth: f32;
-        if params.fractal_type == FRACTAL_MANDELBROT {
-            depth = get_depth(
-                params.point_real,
-                params.point_imag,
-                real,
-                imag,
+fn fragment_iterate(input: VertexOutput) -> @location(0) vec4<f32> {
+    iterate_pixel(vec2<u32>(input.position.xy), input.fragment_position);
+    // the color target is write masked out, this is just to have an output
+    return vec4(0.0);
+}
+
+// the iteration pass for the compute backend, with a thread per pixel of the slice
+@compute @workgroup_size(workgroup_size_x, workgroup_size_y)
+fn compute_iterate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = vec2(params.width, screen_height());
+    let pixel = vec2(id.x, id.y + params.slice_start);
+    if pixel.x >= size.x || pixel.y >= params.slice_end {
+        return;
+    }
+    // the same position the vertex shader gives the pixel's center, with y going up
+    let fragment_position = (vec2<f32>(pixel) + 0.5) / vec2<f32>(size) * vec2(2.0, -2.0) + vec2(-1.0, 1.0);
+    iterate_pixel(pixel, fragment_position);
+}
+
+// shared by both iteration passes.
+// only iterates the pixels a pan brought on screen, or the ones a resume has more depth for.
+fn iterate_pixel(pixel: vec2<u32>, fragment_position: vec2<f32>) {
+    let index = pixel.y * params.width + pixel.x;
+    if params.reuse_previous != 0 {
+        let source = vec2<i32>(pixel) - vec2(params.shift_x, params.shift_y);
+        let size = vec2(i32(params.width), i32(screen_height()));
+        if all(source >= vec2(0)) && all(source < size) {
+            store_orbit(index, previous_orbits[u32(source.y) * params.width + u32(source.x)]);
+            return;
+        }
+    }
+    if params.resume_previous != 0 {
+        let previous = orbits[index];
+        if (previous.flags & (ORBIT_ESCAPED | ORBIT_PERIODIC)) != 0 {
+            // still counted, since the histogram starts over
+            store_orbit(index, previous);
+            return;
+        }
+    }
+    let screen_size = vec2<f32>(f32(params.width), f32(screen_height()));
+    let jitter = vec2(params.jitter_x, -params.jitter_y) * 2.0 / screen_size;
+    let orbit = iterate(fragment_position + jitter, index);
+    store_orbit(index, orbit);
+    if (orbit.flags & (ORBIT_ESCAPED | ORBIT_PERIODIC)) != 0 && orbit.depth >= f32(params.changed_depth) {
+        atomicAdd(&stats.changed, 1u);
+    }
+}
+
+// also counts the orbit in the histogram, just for the first sample so they're all the same,
+// and in the stats
+fn store_orbit(index: u32, orbit: Orbit) {
+    orbits[index] = orbit;
+    if (orbit.flags & ORBIT_ESCAPED) != 0 && params.sample_count == 1 {
+        let bin = min(u32(histogram_position(orbit.depth)), HISTOGRAM_BINS - 1);
+        atomicAdd(&histogram[bin], 1u);
+    }
+    if (orbit.flags & ORBIT_GLITCHED) != 0 {
+        atomicAdd(&stats.glitched, 1u);
+        atomicMax(&stats.glitch_pixel, index + 1);
+    }
+    if (orbit.flags & (ORBIT_ESCAPED | ORBIT_PERIODIC)) == 0 {
+        atomicAdd(&stats.unresolved, 1u);
+    } else if (orbit.flags & ORBIT_ESCAPED) != 0 {
+        let fraction = orbit.depth / f32(params.max_depth);
+        if fraction >= 0.25 {
+            atomicAdd(&stats.past_quarter, 1u);
+        }
+        if fraction >= 0.5 {
+            atomicAdd(&stats.late, 1u);
+        }
+    }
+}
+
+// params only has the width, the orbit buffer is exactly the size of the screen
+fn screen_height() -> u32 {
+    return arrayLength(&orbits) / params.width;
+}
+
+// the coloring pass, which is cheap enough to rerun whenever the coloring changes
+@fragment
+fn fragment_color(input: VertexOutput) -> @location(0) vec4<f32> {
+    let index = orbit_index(input.position);
+    let orbit = orbits[index];
+    let color = light(color_of_orbit(orbit), orbit, vec2<i32>(input.position.xy));
+    // averaged before the target's srgb encoding
+    var sum = color;
+    if params.sample_count > 1 {
+        sum += accumulation[index];
+    }
+    accumulation[index] = sum;
+    return sum / f32(params.sample_count);
+}
+
+fn orbit_index(position: vec4<f32>) -> u32 {
+    let pixel = vec2<u32>(position.xy);
+    return pixel.y * params.width + pixel.x;
+}
+
+// the pixel's orbit, clamped to the edges of the screen
+fn orbit_at(pixel: vec2<i32>) -> Orbit {
+    let size = vec2(i32(params.width), i32(screen_height()));
+    let clamped = vec2<u32>(clamp(pixel, vec2(0), size - 1));
+    return orbits[clamped.y * params.width + clamped.x];
+}
+
+// the last stage of the coloring pass, which shades the color as if it were painted on a surface
+fn light(color: vec4<f32>, orbit: Orbit, pixel: vec2<i32>) -> vec4<f32> {
+    if params.lighting_mode == LIGHTING_NONE || (orbit.flags & ORBIT_ESCAPED) == 0 {
+        return color;
+    }
+    // the slope points away from the set, which is the top of the surface
+    var slope: vec2<f32>;
+    switch params.lighting_mode {
+        case LIGHTING_DISTANCE: {
+            // z / derivative, which points the way the distance grows
+            let z = orbit.z;
+            let derivative = orbit.derivative;
+            slope = normalize(vec2(
+                z.x * derivative.x + z.y * derivative.y,
+                z.y * derivative.x - z.x * derivative.y,
+            ));
+        }
+        default: {
+            // pixel rows go down while imag goes up
+            slope = -0.5 * vec2(
+                surface_height(orbit_at(pixel + vec2(1, 0))) - surface_height(orbit_at(pixel - vec2(1, 0))),
+                surface_height(orbit_at(pixel - vec2(0, 1))) - surface_height(orbit_at(pixel + vec2(0, 1))),
             );
+        }
+    }
+    let normal = normalize(vec3(slope * params.relief, 1.0));
+    let to_light = vec3(params.light_x, params.light_y, params.light_z);
+    // blinn-phong, looking straight down at the screen
+    let diffuse = max(dot(normal, to_light), 0.0);
+    let halfway = normalize(to_light + vec3(0.0, 0.0, 1.0));
+    let specular = params.specular * pow(max(dot(normal, halfway), 0.0), SHININESS);
+    return vec4(color.rgb * (AMBIENT + (1.0 - AMBIENT) * diffuse) + specular, color.a);
+}
+
+// for LIGHTING_DEPTH, with the points that don't escape as high as they get
+fn surface_height(orbit: Orbit) -> f32 {
+    if (orbit.flags & ORBIT_ESCAPED) == 0 {
+        return log(1.0 + f32(params.max_depth));
+    }
+    return log(1.0 + max(orbit.depth, 0.0));
+}
+
+// index is for the pixel's resume
+fn iterate(fragment_position: vec2<f32>, index: u32) -> Orbit {
+    let real: f32 = params.center_real + fragment_position.x * params.radius_real;
+    let imag: f32 = params.center_imag + fragment_position.y * params.radius_imag;
+    // how the pixel's z0 or c moves with its position, see Orbit.derivative
+    let radius = vec2(params.radius_real, 0.0);
+    if params.precision_mode == PRECISION_PERTURBATION {
+        // real and imag are the offset from the reference in units of 2^radius_exponent
+        let offset = vec2(real, imag);
+        // the zero offsets still get the same exponent
+        // so the other one doesn't get scaled out of the range of an f32
+        if params.fractal_type == FRACTAL_MANDELBROT {
+            return get_depth_perturbed(vec2(0.0), params.radius_exponent, offset, params.radius_exponent, vec2(0.0), radius);
         } else {
-            depth = get_depth(
-                real,
-                imag,
-                params.point_real,
-                params.point_imag,
-            );
+            return get_depth_perturbed(offset, params.radius_exponent, vec2(0.0), params.radius_exponent, radius, vec2(0.0));
+        }
+    } else if params.precision_mode == PRECISION_DOUBLE {
+        // the pixel's offset from the center is small enough that f32 is fine for it
+        let offset = fragment_position * vec2(params.radius_real, params.radius_imag);
+        return get_depth_f64(offset, radius);
+    } else if params.precision_mode == PRECISION_DOUBLE_SINGLE {
+        // the pixel's offset from the center is small enough that f32 is fine for it
+        let pixel = vec4(
+            ds_add(vec2(params.center_real, params.center_real_lo), vec2(fragment_position.x * params.radius_real, 0.0)),
+            ds_add(vec2(params.center_imag, params.center_imag_lo), vec2(fragment_position.y * params.radius_imag, 0.0)),
+        );
+        let point = vec4(params.point_real, params.point_real_lo, params.point_imag, params.point_imag_lo);
+        var z0 = point;
+        var c = pixel;
+        var dz0 = vec2(0.0);
+        var dc = radius;
+        if params.fractal_type == FRACTAL_JULIA_SET {
+            z0 = pixel;
+            c = point;
+            dz0 = radius;
+            dc = vec2(0.0);
+        }
+        return get_depth_ds(z0, c, dz0, dc);
+    } else if params.fractal_type == FRACTAL_MANDELBROT || params.fractal_type == FRACTAL_JULIA_SET  {
+        var state = resume_new(vec2(params.point_real, params.point_imag), vec2(0.0));
+        var c = vec2(real, imag);
+        var dc = radius;
+        if params.fractal_type == FRACTAL_JULIA_SET {
+            state = resume_new(vec2(real, imag), radius);
+            c = vec2(params.point_real, params.point_imag);
+            dc = vec2(0.0);
+        }
+        if params.resume_previous != 0 {
+            state = resumes[index];
         }
-        return color_of_depth(depth);
+        let orbit = get_depth_from(&state, c.x, c.y, dc);
+        resumes[index] = state;
+        return orbit;
     } else if params.fractal_type == FRACTAL_METABROT || params.fractal_type == FRACTAL_METAJULIA {
+        // the sub-fractals don't move smoothly with the pixel, so there's no derivative
+        var best = Orbit(0.0, 0, vec2(0.0), vec2(0.0), vec2(0.0), 0, MAX_F32, 0.0);
         var best_depth: f32 = 0.0;
         var best_real: f32 = 0.0;
         var best_imag: f32 = 0.0;
@@ -171,26 +1186,32 @@ fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
                 // TODO: this may only be correct for the metabrot
                 let sub_real = (4.0 * f32(row) / f32(params.sub_fractal_width) - 2.0) + (imag * imag - real * real);
                 let sub_imag = (4.0 * f32(col) / f32(params.sub_fractal_width) - 2.0) + (2.0 * real * imag);
-                var depth: f32;
+                var orbit: Orbit;
                 if params.fractal_type == FRACTAL_METABROT {
-                    depth = get_depth(
+                    orbit = get_depth(
                         real,
                         imag,
                         sub_real,
                         sub_imag,
+                        vec2(0.0),
+                        vec2(0.0),
                     );
                 } else {
-                    depth = get_depth(
+                    orbit = get_depth(
                         sub_real,
                         sub_imag,
                         real,
                         imag,
+                        vec2(0.0),
+                        vec2(0.0),
                     );
                 }
-                if depth >= f32(params.max_depth) {
-                    return color_of_depth(depth);
+                if (orbit.flags & ORBIT_ESCAPED) == 0 {
+                    return orbit;
                 }
+                let depth = orbit.depth;
                 if depth > best_depth {
+                    best = orbit;
                     best_depth = depth;
                     best_real = sub_real;
                     best_imag = sub_imag;
@@ -206,33 +1227,40 @@ fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
                 let radius = sqrt(params.escape_radius_2);
                 let sub_real = best_real + (2.0 * radius * f32(row) / f32(params.sub_fractal_width) - radius) / f32(params.sub_fractal_width);
                 let sub_imag = best_imag + (2.0 * radius * f32(col) / f32(params.sub_fractal_width) - radius) / f32(params.sub_fractal_width);
-                var depth: f32;
+                var orbit: Orbit;
                 if params.fractal_type == FRACTAL_METABROT {
-                    depth = get_depth(
+                    orbit = get_depth(
                         real,
                         imag,
                         sub_real,
                         sub_imag,
+                        vec2(0.0),
+                        vec2(0.0),
                     );
                 } else {
-                    depth = get_depth(
+                    orbit = get_depth(
                         sub_real,
                         sub_imag,
                         real,
                         imag,
+                        vec2(0.0),
+                        vec2(0.0),
                     );
                 }
-                if depth >= f32(params.max_depth) {
-                    return color_of_depth(depth);
+                if (orbit.flags & ORBIT_ESCAPED) == 0 {
+                    return orbit;
                 }
+                let depth = orbit.depth;
                 if depth > best_depth {
+                    best = orbit;
                     best_depth = depth;
                 }
             }
         }
-        return color_of_depth(best_depth);
+        return best;
     } else {
-        return vec4<f32>(1.0, 0.0, 0.0, 1.0);
+        // shouldn't happen, but it makes the whole thing black instead of undefined
+        return Orbit(0.0, 0, vec2(0.0), vec2(0.0), vec2(0.0), 0, MAX_F32, 0.0);
     }
 }
 
@@ -245,7 +1273,7 @@ fn rainbow(t: f32) -> vec4<f32> {
 }
 
 fn cubehelix(c: vec3<f32>) -> vec4<f32> {
-    let h = (c.x + 120.0) * 3.1415926535897932384626433 / 180.0;
+    let h = (c.x + 120.0) * PI / 180.0;
     let l = c.z;
     let a = c.y * l * (1.0 - l);
     let cosh = cos(h);
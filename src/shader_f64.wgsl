@@ -0,0 +1,66 @@
+// only compiled in when the adapter supports SHADER_F64,
+// otherwise shader_f64_stub.wgsl is used instead
+
+// get_depth in native f64, for the pixel at offset from the center.
+// the three f32s the center is split into add up to it exactly in f64.
+fn get_depth_f64(offset: vec2<f32>, radius: vec2<f32>) -> Orbit {
+    let pixel_real = f64(params.center_real) + f64(params.center_real_lo) + f64(params.center_real_lo2) + f64(offset.x);
+    let pixel_imag = f64(params.center_imag) + f64(params.center_imag_lo) + f64(params.center_imag_lo2) + f64(offset.y);
+    var z_real = (f64(params.point_real) + f64(params.point_real_lo));
+    var z_imag = (f64(params.point_imag) + f64(params.point_imag_lo));
+    var c_real = pixel_real;
+    var c_imag = pixel_imag;
+    var derivative = vec2(0.0);
+    var dc = radius;
+    if params.fractal_type == FRACTAL_JULIA_SET {
+        z_real = pixel_real;
+        z_imag = pixel_imag;
+        c_real = (f64(params.point_real) + f64(params.point_real_lo));
+        c_imag = (f64(params.point_imag) + f64(params.point_imag_lo));
+        derivative = radius;
+        dc = vec2(0.0);
+    }
+    var z_real2 = z_real * z_real;
+    var z_imag2 = z_imag * z_imag;
+    var trap = trap_distance(vec2(f32(z_real), f32(z_imag)));
+    var average = average_new(vec2(f32(z_real), f32(z_imag)));
+    let escape_radius_2 = f64(params.escape_radius_2);
+    for (var depth: u32 = 0; depth < params.max_depth; depth++) {
+        if (z_real2 + z_imag2 > escape_radius_2) {
+            return Orbit(smooth_depth(depth, f32(z_real2 + z_imag2)), ORBIT_ESCAPED, vec2(f32(z_real), f32(z_imag)), derivative, vec2(0.0), 0, trap, average_value(average, vec2(f32(z_real), f32(z_imag))));
+        }
+        derivative = step_derivative(vec2(f32(z_real), f32(z_imag)), derivative, dc);
+        let cross = z_real * z_imag;
+        switch params.formula {
+            case FORMULA_BURNING_SHIP: {
+                z_imag = abs(cross + cross) + c_imag;
+                z_real = z_real2 - z_imag2 + c_real;
+            }
+            case FORMULA_TRICORN: {
+                z_imag = -(cross + cross) + c_imag;
+                z_real = z_real2 - z_imag2 + c_real;
+            }
+            case FORMULA_CELTIC: {
+                z_imag = cross + cross + c_imag;
+                z_real = abs(z_real2 - z_imag2) + c_real;
+            }
+            case FORMULA_BUFFALO: {
+                z_imag = -abs(cross + cross) + c_imag;
+                z_real = abs(z_real2 - z_imag2) + c_real;
+            }
+            case FORMULA_PERPENDICULAR: {
+                z_imag = -(abs(z_real) + abs(z_real)) * z_imag + c_imag;
+                z_real = z_real2 - z_imag2 + c_real;
+            }
+            default: {
+                z_imag = cross + cross + c_imag;
+                z_real = z_real2 - z_imag2 + c_real;
+            }
+        }
+        z_real2 = z_real * z_real;
+        z_imag2 = z_imag * z_imag;
+        trap = min(trap, trap_distance(vec2(f32(z_real), f32(z_imag))));
+        average_add(&average, vec2(f32(z_real), f32(z_imag)), vec2(f32(c_real), f32(c_imag)));
+    }
+    return Orbit(f32(params.max_depth), 0, vec2(f32(z_real), f32(z_imag)), derivative, vec2(0.0), 0, trap, average_value(average, vec2(f32(z_real), f32(z_imag))));
+}
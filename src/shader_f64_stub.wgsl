@@ -0,0 +1,6 @@
+// used instead of shader_f64.wgsl when the adapter doesn't support SHADER_F64.
+// the cpu never picks the f64 precision then, so this is never called.
+
+fn get_depth_f64(offset: vec2<f32>, radius: vec2<f32>) -> Orbit {
+    return Orbit(0.0, 0, vec2(0.0), vec2(0.0), vec2(0.0), 0, MAX_F32, 0.0);
+}
@@ -0,0 +1,72 @@
+use eframe::wgpu;
+
+use crate::Readback;
+
+/// counted by the iteration pass, mirrored by Stats in shader.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct StatCounts {
+    /// the pixels that escaped or got caught in a cycle at or past the pass's changed_depth,
+    /// leaving out the ones copied from before a pan or a resume
+    pub(crate) changed: u32,
+    /// the pixels that got to max_depth without escaping or getting caught in a cycle
+    pub(crate) unresolved: u32,
+    /// the pixels that escaped past half of max_depth
+    pub(crate) late: u32,
+    /// the pixels that escaped past a quarter of max_depth, including the late ones
+    pub(crate) past_quarter: u32,
+    /// the pixels the perturbation glitched on
+    pub(crate) glitched: u32,
+    /// the index of the last of them plus one, so 0 is none
+    pub(crate) glitch_pixel: u32,
+}
+
+/// what the last iteration pass counted, read back a frame or so later like the histogram
+pub(crate) struct IterationStats {
+    pub(crate) readback: Readback,
+    /// how many pixels the pass in the buffer had, and the one being read back
+    pixels: u32,
+    readback_pixels: u32,
+}
+impl IterationStats {
+    pub(crate) fn new(device: &wgpu::Device) -> Self {
+        Self {
+            readback: Readback::new(device, "stats", std::mem::size_of::<StatCounts>() as u64),
+            pixels: 0,
+            readback_pixels: 0,
+        }
+    }
+
+    /// zeroes the counts before an iteration pass over that many pixels
+    pub(crate) fn clear(&mut self, command_encoder: &mut wgpu::CommandEncoder, pixels: u32) {
+        self.readback.clear(command_encoder);
+        self.pixels = pixels;
+    }
+
+    /// copies the counts to be read back if they're from a pass we haven't read yet.
+    /// call start_readback after submitting.
+    pub(crate) fn encode_readback(&mut self, command_encoder: &mut wgpu::CommandEncoder) {
+        if self.readback.encode(command_encoder) {
+            self.readback_pixels = self.pixels;
+        }
+    }
+
+    pub(crate) fn start_readback(&mut self) {
+        self.readback.start();
+    }
+
+    /// whether there's a pass whose counts haven't come back yet
+    pub(crate) fn is_pending(&self) -> bool {
+        self.readback.is_pending()
+    }
+
+    /// the counts and how many pixels they're out of, if the readback finished.
+    /// a failed one just waits for the next pass's.
+    pub(crate) fn poll(&mut self, device: &wgpu::Device) -> Option<(StatCounts, u32)> {
+        let counts = self
+            .readback
+            .poll(device, bytemuck::pod_read_unaligned)?
+            .ok()?;
+        Some((counts, self.readback_pixels))
+    }
+}
@@ -0,0 +1,53 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use eframe::wgpu;
+
+/// a png loaded from disk for the texture mapping, as srgb rgba
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct TextureImage {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) texels: Vec<[u8; 4]>,
+}
+impl TextureImage {
+    /// a white pixel, for when there's no image yet
+    pub(crate) fn blank() -> Self {
+        Self {
+            width: 1,
+            height: 1,
+            texels: vec![[255; 4]],
+        }
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let mut decoder = png::Decoder::new(BufReader::new(file));
+        // everything comes out as 8 bit gray or rgb, with alpha
+        decoder.set_transformations(
+            png::Transformations::EXPAND
+                | png::Transformations::STRIP_16
+                | png::Transformations::ALPHA,
+        );
+        let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+        let mut buffer = vec![0; reader.output_buffer_size().ok_or("the image is too big")?];
+        let info = reader.next_frame(&mut buffer).map_err(|e| e.to_string())?;
+        let max_size = wgpu::Limits::default().max_texture_dimension_2d;
+        if info.width > max_size || info.height > max_size {
+            return Err(format!("the image is bigger than {max_size}x{max_size}"));
+        }
+        let bytes = &buffer[..info.buffer_size()];
+        let texels = match info.color_type {
+            png::ColorType::Rgba => bytemuck::cast_slice(bytes).to_vec(),
+            png::ColorType::GrayscaleAlpha => bytes
+                .chunks_exact(2)
+                .map(|gray_alpha| [gray_alpha[0], gray_alpha[0], gray_alpha[0], gray_alpha[1]])
+                .collect(),
+            color_type => return Err(format!("unexpected color type {color_type:?}")),
+        };
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            texels,
+        })
+    }
+}